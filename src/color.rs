@@ -0,0 +1,118 @@
+use crate::{DEBUG, ERROR, FATAL, INFO, TRACE, WARNING};
+
+/// ANSI terminal color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Color {
+    fn code(self) -> u8 {
+        match self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::BrightBlack => 90,
+            Color::BrightRed => 91,
+            Color::BrightGreen => 92,
+            Color::BrightYellow => 93,
+            Color::BrightBlue => 94,
+            Color::BrightMagenta => 95,
+            Color::BrightCyan => 96,
+            Color::BrightWhite => 97,
+        }
+    }
+}
+
+/// Whether console output is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color if the output stream is a terminal. `NO_COLOR` disables
+    /// colors, `CLICOLOR_FORCE` enables them even if it is not a terminal.
+    #[default]
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolve [`ColorChoice::Auto`] for a stream
+    pub(crate) fn enabled(self, terminal: bool) -> bool {
+        let set = |var: &str| std::env::var_os(var).is_some_and(|v| !v.is_empty() && v != "0");
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if set("NO_COLOR") => false,
+            ColorChoice::Auto if set("CLICOLOR_FORCE") => true,
+            ColorChoice::Auto => terminal,
+        }
+    }
+}
+
+/// Console color of every level, `None` leaves the level uncolored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colors {
+    pub trace: Option<Color>,
+    pub debug: Option<Color>,
+    pub info: Option<Color>,
+    pub warning: Option<Color>,
+    pub error: Option<Color>,
+    /// Fatal messages are also bold
+    pub fatal: Option<Color>,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            trace: Some(Color::BrightBlack),
+            debug: Some(Color::Cyan),
+            info: Some(Color::Green),
+            warning: Some(Color::Yellow),
+            error: Some(Color::Red),
+            fatal: Some(Color::BrightRed),
+        }
+    }
+}
+
+impl Colors {
+    /// Wrap `line` in the escape codes for `level`
+    pub(crate) fn paint(&self, line: &str, level: u32) -> String {
+        let color = match level {
+            TRACE => self.trace,
+            DEBUG => self.debug,
+            INFO => self.info,
+            WARNING => self.warning,
+            ERROR => self.error,
+            FATAL => self.fatal,
+            _ => None,
+        };
+        match (color, level) {
+            (Some(color), FATAL) => format!("\x1b[1;{}m{line}\x1b[0m", color.code()),
+            (Some(color), _) => format!("\x1b[{}m{line}\x1b[0m", color.code()),
+            (None, FATAL) => format!("\x1b[1m{line}\x1b[0m"),
+            (None, _) => line.to_string(),
+        }
+    }
+}
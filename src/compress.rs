@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+
+/// Compression applied to rotated log files
+///
+/// The active log file is never compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Keep rotated files as they are
+    #[default]
+    None,
+    /// gzip, adds `.gz` to the file name
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, adds `.zst` to the file name
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// Suffix added to compressed files, empty for [`Compression::None`]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => ".gz",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    /// Compress `path` to `path` + extension and remove it
+    fn compress(self, path: &str) -> io::Result<()> {
+        if self == Compression::None {
+            return Ok(());
+        }
+        let target = format!("{path}{}", self.extension());
+        let tmp = format!("{target}.tmp");
+        let input = File::open(path)?;
+        let output = File::create(&tmp)?;
+        match self {
+            Compression::None => drop((input, output)),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(output, flate2::Compression::default());
+                io::copy(&mut &input, &mut encoder)?;
+                encoder.finish()?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(output, 0)?;
+                io::copy(&mut &input, &mut encoder)?;
+                encoder.finish()?;
+            }
+        }
+        std::fs::rename(&tmp, &target)?;
+        std::fs::remove_file(path)
+    }
+}
+
+type Job = Box<dyn FnOnce(Compression) + Send>;
+
+/// Compresses rotated files on a background thread
+///
+/// Jobs run in the order they were queued, so files can be renamed or
+/// removed by a job after the previous ones compressed them.
+#[derive(Debug)]
+pub(crate) struct Compressor {
+    compression: Compression,
+    jobs: Mutex<Option<Sender<Job>>>,
+    /// Number of jobs queued or running
+    pending: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl Compressor {
+    pub(crate) fn new(compression: Compression) -> Self {
+        Self {
+            compression,
+            jobs: Mutex::new(None),
+            pending: Arc::new((Mutex::new(0), Condvar::new())),
+        }
+    }
+
+    /// Returns `true` unless the compression is [`Compression::None`]
+    pub(crate) fn enabled(&self) -> bool {
+        self.compression != Compression::None
+    }
+
+    /// Queue `path` for compression
+    pub(crate) fn compress(&self, path: &str) {
+        if !self.enabled() || !Path::new(path).exists() {
+            return;
+        }
+        let path = path.to_string();
+        self.run(move |compression| compress(compression, &path));
+    }
+
+    /// Queue a job, the thread is started on the first call. Does
+    /// nothing if compression is disabled.
+    pub(crate) fn run(&self, job: impl FnOnce(Compression) + Send + 'static) {
+        if !self.enabled() {
+            return;
+        }
+        let mut jobs = self.jobs.lock().unwrap_or_else(PoisonError::into_inner);
+        if jobs.is_none() {
+            *jobs = self.spawn();
+        }
+        let Some(tx) = jobs.as_ref() else {
+            return;
+        };
+        *self
+            .pending
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) += 1;
+        if tx.send(Box::new(job)).is_err() {
+            *self
+                .pending
+                .0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) -= 1;
+        }
+    }
+
+    /// Wait until all queued jobs are done
+    pub(crate) fn wait(&self) {
+        let (pending, done) = &*self.pending;
+        let mut pending = pending.lock().unwrap_or_else(PoisonError::into_inner);
+        while *pending > 0 {
+            pending = done.wait(pending).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn spawn(&self) -> Option<Sender<Job>> {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let compression = self.compression;
+        let pending = self.pending.clone();
+        let result = std::thread::Builder::new()
+            .name("se-logger compress".to_string())
+            .spawn(move || {
+                for job in rx {
+                    job(compression);
+                    let (pending, done) = &*pending;
+                    *pending.lock().unwrap_or_else(PoisonError::into_inner) -= 1;
+                    done.notify_all();
+                }
+            });
+        match result {
+            Ok(_) => Some(tx),
+            Err(e) => {
+                eprintln!("Logger: Failed to start compression thread: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Compress `path`, reporting failures
+pub(crate) fn compress(compression: Compression, path: &str) {
+    if let Err(e) = compression.compress(path) {
+        eprintln!("Logger: Failed to compress {path}: {e}");
+    }
+}
@@ -0,0 +1,588 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use toml::{Table, Value};
+
+use crate::file::PATH_VAR;
+use crate::{
+    ColorChoice, Compression, Console, Filter, FlushPolicy, Format, Level, LoggerBuilder,
+    OverflowPolicy, Pattern, Retention, Rotation,
+};
+
+/// Logger settings loaded from a TOML file
+///
+/// Every key is optional, unknown keys are rejected:
+/// ```toml
+/// level = "info"            # Default level of every target
+/// format = "bracket"        # bracket, json, logfmt or a pattern like "{d} {l} {m}"
+/// color = "auto"            # auto, always or never
+///
+/// [filter]                  # Or a string: filter = "info,myapp::db=trace"
+/// "myapp::db" = "trace"
+/// hyper = "warning"
+///
+/// [console]
+/// enabled = true
+/// target = "split"          # stdout, stderr or split
+/// stderr_level = "warning"  # Split level, with target = "split"
+/// level = "debug"
+/// format = "logfmt"
+///
+/// [file]
+/// enabled = true
+/// path = "logs/app_%F.log"
+/// level = "debug"
+/// format = "json"
+/// flush = "5s"              # always, a number of records or an interval
+/// flush_level = "error"
+/// max_size = "10M"          # Bytes, or a number with a K, M or G suffix
+/// max_files = 5
+/// rotation = "daily"        # never, hourly, daily or on_change
+/// compression = "gzip"      # none, gzip or zstd, if the feature is enabled
+///
+/// [file.retention]
+/// max_files = 30
+/// max_age = "7d"            # Seconds, or a number with a ms, s, m, h or d suffix
+/// max_total_size = "1G"
+/// archive_dir = "logs/archive"
+///
+/// [async]
+/// capacity = 1024
+/// overflow = "block"        # block, drop_newest or drop_oldest
+/// ```
+/// `level` is the default level of the filter, see
+/// [`LoggerBuilder::filter`], so a filter in `SE_LOG_LEVEL` replaces it.
+///
+/// Settings are applied in this order, later ones take precedence:
+/// the defaults, the config file, the `SE_LOG_LEVEL` and `SE_LOG_PATH`
+/// environment variables and the [`LoggerBuilder`] methods called on the
+/// builder returned by [`LoggerBuilder::from_config`].
+/// ```no_run
+/// use se_logger::*;
+///
+/// let config = Config::load("logging.toml").unwrap();
+/// LoggerBuilder::from_config(&config)
+///     .console(false)
+///     .build()
+///     .install();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    level: Option<u32>,
+    filter: Option<Filter>,
+    format: Option<Format>,
+    color: Option<ColorChoice>,
+    console: ConsoleConfig,
+    file: FileConfig,
+    queue: Option<(usize, OverflowPolicy)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ConsoleConfig {
+    enabled: Option<bool>,
+    target: Option<Console>,
+    level: Option<u32>,
+    format: Option<Format>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FileConfig {
+    enabled: Option<bool>,
+    path: Option<String>,
+    level: Option<u32>,
+    format: Option<Format>,
+    flush_policy: Option<FlushPolicy>,
+    flush_level: Option<u32>,
+    max_size: Option<u64>,
+    max_files: Option<usize>,
+    rotation: Option<Rotation>,
+    compression: Option<Compression>,
+    retention: Option<Retention>,
+}
+
+/// Error returned when loading an invalid [`Config`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Dotted path of the offending key, empty if the error is not
+    /// caused by a single key
+    pub key: String,
+    /// Description of the error
+    pub message: String,
+}
+
+impl Config {
+    /// Read and parse a config file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            ConfigError::new("", &format!("failed to read {}: {e}", path.display()))
+        })?;
+        Self::parse(&text)
+    }
+
+    /// Parse a config, see [`Config`] for the keys
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let table: Table = text.parse().map_err(|e: toml::de::Error| {
+            let message = match e.message().trim() {
+                "" => "invalid TOML".to_string(),
+                message => message.replace('\n', ", "),
+            };
+            let message = match e.span() {
+                Some(span) => {
+                    let line = text[..span.start].matches('\n').count() + 1;
+                    format!("line {line}: {message}")
+                }
+                None => message,
+            };
+            ConfigError::new("", &message)
+        })?;
+        let mut config = Config::default();
+        let mut stderr_level = None;
+        let mut directives = None;
+        for (key, value) in &table {
+            match key.as_str() {
+                "level" => config.level = Some(level(key, value)?),
+                "filter" => directives = Some(filter(key, value)?),
+                "format" => config.format = Some(format(key, value)?),
+                "color" => {
+                    config.color = Some(choice(
+                        key,
+                        value,
+                        &[
+                            ("auto", ColorChoice::Auto),
+                            ("always", ColorChoice::Always),
+                            ("never", ColorChoice::Never),
+                        ],
+                    )?)
+                }
+                "console" => {
+                    for (name, value) in table_of(key, value)? {
+                        let key = &format!("console.{name}");
+                        match name.as_str() {
+                            "enabled" => config.console.enabled = Some(boolean(key, value)?),
+                            "target" => {
+                                config.console.target = Some(choice(
+                                    key,
+                                    value,
+                                    &[
+                                        ("stdout", Console::Stdout),
+                                        ("stderr", Console::Stderr),
+                                        ("split", Console::WARNING_TO_STDERR),
+                                    ],
+                                )?)
+                            }
+                            "stderr_level" => stderr_level = Some(level(key, value)?),
+                            "level" => config.console.level = Some(level(key, value)?),
+                            "format" => config.console.format = Some(format(key, value)?),
+                            _ => return Err(unknown(key)),
+                        }
+                    }
+                }
+                "file" => config.file = file(key, value)?,
+                "async" => config.queue = Some(queue(key, value)?),
+                _ => return Err(unknown(key)),
+            }
+        }
+        if let Some(level) = stderr_level {
+            match config.console.target {
+                Some(Console::Split(_)) => config.console.target = Some(Console::Split(level)),
+                _ => {
+                    return Err(ConfigError::new(
+                        "console.stderr_level",
+                        "requires `console.target = \"split\"`",
+                    ))
+                }
+            }
+        }
+        if let Some(mut directives) = directives {
+            // A default level in the directives takes precedence
+            if let Some(level) = config.level {
+                directives = format!("{level},{directives}");
+            }
+            config.filter = Some(
+                Filter::parse(&directives).map_err(|e| ConfigError::new("filter", &e.message))?,
+            );
+        }
+        Ok(config)
+    }
+
+    /// Returns `true` if `other` only differs in levels and filters,
+    /// which can be changed without rebuilding the logger
+    pub(crate) fn same_sinks(&self, other: &Config) -> bool {
+        self.without_levels() == other.without_levels()
+    }
+
+    fn without_levels(&self) -> Config {
+        let mut config = self.clone();
+        config.level = None;
+        config.filter = None;
+        config.console.level = None;
+        config.file.level = None;
+        config
+    }
+
+    /// Apply the settings to `builder`
+    fn apply(&self, mut builder: LoggerBuilder) -> LoggerBuilder {
+        match (&self.filter, self.level) {
+            (Some(filter), _) => builder = builder.filter(filter.clone()),
+            (None, Some(level)) => builder = builder.filter(Filter::new(level)),
+            (None, None) => {}
+        }
+        if let Some(format) = &self.format {
+            builder = builder.format(format.clone());
+        }
+        if let Some(color) = self.color {
+            builder = builder.color(color);
+        }
+
+        let console = &self.console;
+        if let Some(target) = console.target {
+            builder = builder.console_target(target);
+        }
+        if let Some(enabled) = console.enabled {
+            builder = match (enabled, console.target) {
+                (true, Some(_)) => builder,
+                (enabled, _) => builder.console(enabled),
+            };
+        }
+        if let Some(level) = console.level {
+            builder = builder.console_level(level);
+        }
+        if let Some(format) = &console.format {
+            builder = builder.console_format(format.clone());
+        }
+
+        let file = &self.file;
+        if let Some(enabled) = file.enabled {
+            builder = builder.file(enabled);
+        }
+        if let Some(path) = &file.path {
+            builder = builder.path(path);
+        }
+        if let Some(level) = file.level {
+            builder = builder.file_level(level);
+        }
+        if let Some(format) = &file.format {
+            builder = builder.file_format(format.clone());
+        }
+        if let Some(policy) = file.flush_policy {
+            builder = builder.flush_policy(policy);
+        }
+        if let Some(level) = file.flush_level {
+            builder = builder.flush_level(level);
+        }
+        if let Some(bytes) = file.max_size {
+            builder = builder.max_file_size(bytes);
+        }
+        if let Some(count) = file.max_files {
+            builder = builder.max_files(count);
+        }
+        if let Some(rotation) = file.rotation {
+            builder = builder.rotation(rotation);
+        }
+        if let Some(compression) = file.compression {
+            builder = builder.compression(compression);
+        }
+        if let Some(retention) = &file.retention {
+            builder = builder.retention(retention.clone());
+        }
+
+        if let Some((capacity, policy)) = self.queue {
+            builder = builder.asynchronous(capacity, policy);
+        }
+        builder
+    }
+}
+
+impl FromStr for Config {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl LoggerBuilder {
+    /// Create a builder with the settings of `config`, overridden by the
+    /// `SE_LOG_LEVEL` and `SE_LOG_PATH` environment variables
+    ///
+    /// Methods called on the returned builder override both.
+    pub fn from_config(config: &Config) -> Self {
+        let mut builder = config.apply(LoggerBuilder::new()).env_filter();
+        if let Ok(path) = std::env::var(PATH_VAR) {
+            builder = builder.path(&path);
+        }
+        builder
+    }
+}
+
+impl ConfigError {
+    fn new(key: &str, message: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.key.as_str() {
+            "" => write!(f, "invalid config: {}", self.message),
+            key => write!(f, "invalid config at `{key}`: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn file(key: &str, value: &Value) -> Result<FileConfig, ConfigError> {
+    let mut config = FileConfig::default();
+    for (name, value) in table_of(key, value)? {
+        let key = &format!("{key}.{name}");
+        match name.as_str() {
+            "enabled" => config.enabled = Some(boolean(key, value)?),
+            "path" => config.path = Some(string(key, value)?.to_string()),
+            "level" => config.level = Some(level(key, value)?),
+            "format" => config.format = Some(format(key, value)?),
+            "flush" => {
+                config.flush_policy = Some(match value {
+                    Value::String(s) if s == "always" => FlushPolicy::Always,
+                    Value::Integer(_) => FlushPolicy::Records(integer(key, value)?),
+                    _ => match duration(key, value)? {
+                        interval if interval.is_zero() => {
+                            return Err(ConfigError::new(key, "interval must not be zero"))
+                        }
+                        interval => FlushPolicy::Interval(interval),
+                    },
+                })
+            }
+            "flush_level" => config.flush_level = Some(level(key, value)?),
+            "max_size" => config.max_size = Some(size(key, value)?),
+            "max_files" => config.max_files = Some(integer(key, value)?),
+            "rotation" => {
+                config.rotation = Some(choice(
+                    key,
+                    value,
+                    &[
+                        ("never", Rotation::Never),
+                        ("hourly", Rotation::Hourly),
+                        ("daily", Rotation::Daily),
+                        ("on_change", Rotation::OnChange),
+                    ],
+                )?)
+            }
+            "compression" => {
+                config.compression = Some(choice(
+                    key,
+                    value,
+                    &[
+                        ("none", Compression::None),
+                        #[cfg(feature = "gzip")]
+                        ("gzip", Compression::Gzip),
+                        #[cfg(feature = "zstd")]
+                        ("zstd", Compression::Zstd),
+                    ],
+                )?)
+            }
+            "retention" => config.retention = Some(retention(key, value)?),
+            _ => return Err(unknown(key)),
+        }
+    }
+    Ok(config)
+}
+
+fn retention(key: &str, value: &Value) -> Result<Retention, ConfigError> {
+    let mut retention = Retention::default();
+    for (name, value) in table_of(key, value)? {
+        let key = &format!("{key}.{name}");
+        match name.as_str() {
+            "max_files" => retention.max_files = Some(integer(key, value)?),
+            "max_age" => retention.max_age = Some(duration(key, value)?),
+            "max_total_size" => retention.max_total_size = Some(size(key, value)?),
+            "archive_dir" => retention.archive_dir = Some(string(key, value)?.into()),
+            _ => return Err(unknown(key)),
+        }
+    }
+    Ok(retention)
+}
+
+fn queue(key: &str, value: &Value) -> Result<(usize, OverflowPolicy), ConfigError> {
+    let mut capacity = 1024;
+    let mut policy = OverflowPolicy::default();
+    for (name, value) in table_of(key, value)? {
+        let key = &format!("{key}.{name}");
+        match name.as_str() {
+            "capacity" => capacity = integer(key, value)?,
+            "overflow" => {
+                policy = choice(
+                    key,
+                    value,
+                    &[
+                        ("block", OverflowPolicy::Block),
+                        ("drop_newest", OverflowPolicy::DropNewest),
+                        ("drop_oldest", OverflowPolicy::DropOldest),
+                    ],
+                )?
+            }
+            _ => return Err(unknown(key)),
+        }
+    }
+    Ok((capacity, policy))
+}
+
+fn unknown(key: &str) -> ConfigError {
+    ConfigError::new(key, "unknown key")
+}
+
+fn table_of<'a>(key: &str, value: &'a Value) -> Result<&'a Table, ConfigError> {
+    value
+        .as_table()
+        .ok_or_else(|| ConfigError::new(key, "expected a table"))
+}
+
+fn string<'a>(key: &str, value: &'a Value) -> Result<&'a str, ConfigError> {
+    value
+        .as_str()
+        .ok_or_else(|| ConfigError::new(key, "expected a string"))
+}
+
+fn boolean(key: &str, value: &Value) -> Result<bool, ConfigError> {
+    value
+        .as_bool()
+        .ok_or_else(|| ConfigError::new(key, "expected `true` or `false`"))
+}
+
+fn integer<T: TryFrom<i64>>(key: &str, value: &Value) -> Result<T, ConfigError> {
+    value
+        .as_integer()
+        .and_then(|n| T::try_from(n).ok())
+        .ok_or_else(|| ConfigError::new(key, "expected a positive integer"))
+}
+
+/// A string out of `choices`
+fn choice<T: Copy>(key: &str, value: &Value, choices: &[(&str, T)]) -> Result<T, ConfigError> {
+    let s = string(key, value)?;
+    match choices
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(s))
+    {
+        Some(&(_, choice)) => Ok(choice),
+        None => {
+            let names: Vec<_> = choices.iter().map(|(name, _)| *name).collect();
+            Err(ConfigError::new(
+                key,
+                &format!("unknown value `{s}`, expected {}", names.join(", ")),
+            ))
+        }
+    }
+}
+
+/// A level name or number
+fn level(key: &str, value: &Value) -> Result<u32, ConfigError> {
+    let level = match value {
+        Value::String(s) => s.parse::<Level>(),
+        Value::Integer(n) => Level::try_from(u32::try_from(*n).unwrap_or(u32::MAX)),
+        _ => return Err(ConfigError::new(key, "expected a level")),
+    };
+    level
+        .map(u32::from)
+        .map_err(|e| ConfigError::new(key, &e.to_string()))
+}
+
+/// A filter string, or a table of targets and levels,
+/// returns the validated filter directives
+fn filter(key: &str, value: &Value) -> Result<String, ConfigError> {
+    let directives = match value {
+        Value::String(s) => s.clone(),
+        Value::Table(table) => {
+            let mut directives = Vec::new();
+            for (target, value) in table {
+                let key = format!("{key}.{target}");
+                let level = match value.as_str() {
+                    Some(s) if s.eq_ignore_ascii_case("off") => "off".to_string(),
+                    _ => level(&key, value)?.to_string(),
+                };
+                directives.push(format!("{target}={level}"));
+            }
+            directives.join(",")
+        }
+        _ => return Err(ConfigError::new(key, "expected a string or a table")),
+    };
+    match Filter::parse(&directives) {
+        Ok(_) => Ok(directives),
+        Err(e) => Err(ConfigError::new(key, &e.to_string())),
+    }
+}
+
+/// A format name or a pattern
+fn format(key: &str, value: &Value) -> Result<Format, ConfigError> {
+    let s = string(key, value)?;
+    match s.to_ascii_lowercase().as_str() {
+        "bracket" => Ok(Format::Bracket),
+        "json" => Ok(Format::Json),
+        "logfmt" => Ok(Format::Logfmt),
+        _ if s.contains('{') => Pattern::parse(s)
+            .map(Format::Pattern)
+            .map_err(|e| ConfigError::new(key, &e.to_string())),
+        _ => Err(ConfigError::new(
+            key,
+            &format!("unknown format `{s}`, expected bracket, json, logfmt or a pattern"),
+        )),
+    }
+}
+
+/// Bytes, or a number with a `K`, `M` or `G` suffix
+fn size(key: &str, value: &Value) -> Result<u64, ConfigError> {
+    let s = match value {
+        Value::Integer(_) => return integer(key, value),
+        Value::String(s) => s.trim(),
+        _ => return Err(ConfigError::new(key, "expected a size")),
+    };
+    let (number, unit) = split_unit(s);
+    let unit = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => 0,
+    };
+    match number.parse::<u64>() {
+        Ok(n) if unit > 0 => n
+            .checked_mul(unit)
+            .ok_or_else(|| ConfigError::new(key, &format!("size `{s}` is too large"))),
+        _ => Err(ConfigError::new(key, &format!("invalid size `{s}`"))),
+    }
+}
+
+/// Seconds, or a number with a `ms`, `s`, `m`, `h` or `d` suffix
+fn duration(key: &str, value: &Value) -> Result<Duration, ConfigError> {
+    let s = match value {
+        Value::Integer(_) => return integer(key, value).map(Duration::from_secs),
+        Value::String(s) => s.trim(),
+        _ => return Err(ConfigError::new(key, "expected a duration")),
+    };
+    let (number, unit) = split_unit(s);
+    let millis = match unit {
+        "ms" => 1,
+        "" | "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        _ => 0,
+    };
+    match number.parse::<u64>() {
+        Ok(n) if millis > 0 => n
+            .checked_mul(millis)
+            .map(Duration::from_millis)
+            .ok_or_else(|| ConfigError::new(key, &format!("duration `{s}` is too long"))),
+        _ => Err(ConfigError::new(key, &format!("invalid duration `{s}`"))),
+    }
+}
+
+fn split_unit(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    (&s[..end], s[end..].trim())
+}
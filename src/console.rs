@@ -0,0 +1,106 @@
+use std::io::{self, IsTerminal, Write};
+
+use crate::{ColorChoice, Colors, Format, Record, Sink, WARNING};
+
+/// Where console output is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Console {
+    /// Everything to stdout
+    #[default]
+    Stdout,
+    /// Everything to stderr
+    Stderr,
+    /// Records with this level or a more severe one to stderr,
+    /// the rest to stdout
+    Split(u32),
+    /// No console output
+    Off,
+}
+
+impl Console {
+    /// Split at `WARNING`: warnings, errors and fatal messages to stderr
+    pub const WARNING_TO_STDERR: Console = Console::Split(WARNING);
+
+    /// Returns `true` if a record with `level` is written to stderr
+    fn stderr(self, level: u32) -> bool {
+        match self {
+            Console::Stderr => true,
+            Console::Split(split) => level <= split,
+            Console::Stdout | Console::Off => false,
+        }
+    }
+}
+
+/// Sink writing to stdout and stderr
+#[derive(Debug, Clone)]
+pub struct ConsoleSink {
+    target: Console,
+    color: ColorChoice,
+    colors: Colors,
+    /// Colors for stdout and stderr, `None` if the stream is not colored
+    resolved: (Option<Colors>, Option<Colors>),
+}
+
+impl ConsoleSink {
+    /// Create a console sink with the default colors
+    pub fn new(target: Console) -> Self {
+        let mut sink = Self {
+            target,
+            color: ColorChoice::default(),
+            colors: Colors::default(),
+            resolved: (None, None),
+        };
+        sink.resolve();
+        sink
+    }
+
+    /// Whether the output is colored
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self.resolve();
+        self
+    }
+
+    /// Colors of the levels
+    pub fn colors(mut self, colors: Colors) -> Self {
+        self.colors = colors;
+        self.resolve();
+        self
+    }
+
+    fn resolve(&mut self) {
+        let stdout = self.color.enabled(std::io::stdout().is_terminal());
+        let stderr = self.color.enabled(std::io::stderr().is_terminal());
+        self.resolved = (stdout.then_some(self.colors), stderr.then_some(self.colors));
+    }
+}
+
+impl Sink for ConsoleSink {
+    fn write(&self, record: &Record, format: &Format) -> io::Result<()> {
+        if self.target == Console::Off {
+            return Ok(());
+        }
+        let line = format.format(record);
+        let stderr = self.target.stderr(record.level);
+        let colors = if stderr {
+            &self.resolved.1
+        } else {
+            &self.resolved.0
+        };
+        let line = match colors {
+            Some(colors) => colors.paint(&line, record.level),
+            None => line,
+        };
+        // Errors such as a closed pipe are not worth reporting
+        let _ = if stderr {
+            writeln!(io::stderr().lock(), "{line}")
+        } else {
+            writeln!(io::stdout().lock(), "{line}")
+        };
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
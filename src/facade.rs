@@ -0,0 +1,67 @@
+use crate::{global, Record, DEBUG, ERROR, INFO, TRACE, WARNING};
+
+/// Forwards records from the `log` crate to the process-global logger
+struct Facade;
+
+static FACADE: Facade = Facade;
+
+/// Install se-logger as the global [`log::Log`] implementation
+///
+/// Records emitted with `log::info!` and friends are written by the
+/// process-global logger, see [`Logger::install`](crate::Logger::install).
+/// Fails if another `log` implementation was already installed.
+pub fn init_log_facade() -> Result<(), log::SetLoggerError> {
+    log::set_logger(&FACADE)?;
+    log::set_max_level(level_filter(global().level()));
+    Ok(())
+}
+
+/// Keep the `log` max level in sync with the global logger level
+pub(crate) fn update_max_level(level: u32) {
+    log::set_max_level(level_filter(level));
+}
+
+impl log::Log for Facade {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        global().enabled_for(metadata.target(), from_log_level(metadata.level()))
+    }
+
+    fn log(&self, record: &log::Record) {
+        let logger = global();
+        let level = from_log_level(record.level());
+        if !logger.enabled_for(record.target(), level) {
+            return;
+        }
+        let mut r = Record::new(level, &record.args().to_string());
+        r.target = record.target().to_string();
+        r.module_path = record.module_path().map(str::to_string);
+        r.file = record.file().map(str::to_string);
+        r.line = record.line();
+        logger.log_record(&r);
+    }
+
+    fn flush(&self) {
+        global().flush();
+    }
+}
+
+fn from_log_level(level: log::Level) -> u32 {
+    match level {
+        log::Level::Error => ERROR,
+        log::Level::Warn => WARNING,
+        log::Level::Info => INFO,
+        log::Level::Debug => DEBUG,
+        log::Level::Trace => TRACE,
+    }
+}
+
+fn level_filter(level: u32) -> log::LevelFilter {
+    match level {
+        TRACE => log::LevelFilter::Trace,
+        DEBUG => log::LevelFilter::Debug,
+        INFO => log::LevelFilter::Info,
+        WARNING => log::LevelFilter::Warn,
+        ERROR => log::LevelFilter::Error,
+        _ => log::LevelFilter::Off,
+    }
+}
@@ -0,0 +1,526 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Duration as ChronoDuration, Local, Timelike};
+
+use crate::compress::{self, Compressor};
+use crate::{current_time_fmt, Compression, Format, Record, Retention, Sink, ERROR, INFO};
+
+pub(crate) const DEFAULT_PATH: &str = "unnamed.log";
+/// Environment variable overriding the path pattern of a configured logger
+#[cfg(feature = "config")]
+pub(crate) const PATH_VAR: &str = "SE_LOG_PATH";
+
+/// When buffered log lines are written to the log file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Flush after every record
+    #[default]
+    Always,
+    /// Flush after the given number of records
+    Records(usize),
+    /// Flush at most the given time after a record was written,
+    /// a zero interval flushes after every record
+    Interval(Duration),
+}
+
+/// When the path pattern is expanded again to switch to a new file
+///
+/// The file is only switched if the expanded name differs from the
+/// current one, so the pattern should contain the matching specifiers,
+/// e.g. `app_%F.log` for [`Rotation::Daily`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    /// Keep the name expanded at startup
+    #[default]
+    Never,
+    /// At the start of every hour
+    Hourly,
+    /// At midnight
+    Daily,
+    /// Whenever the expanded name changes, checked at most once a second
+    OnChange,
+}
+
+/// Settings of the log file
+#[derive(Debug, Clone)]
+pub(crate) struct FileConfig {
+    /// Path pattern, expanded with [`current_time_fmt`]
+    pub(crate) pattern: String,
+    pub(crate) flush_policy: FlushPolicy,
+    pub(crate) flush_level: u32,
+    /// Rotate when the file would grow past this size in bytes
+    pub(crate) max_size: Option<u64>,
+    /// Number of rotated files (`name.1`, `name.2`, ...) to keep
+    pub(crate) max_files: usize,
+    pub(crate) rotation: Rotation,
+    pub(crate) retention: Option<Retention>,
+    pub(crate) compression: Compression,
+}
+
+/// Sink writing to a log file, kept open with a buffered writer
+///
+/// Created with [`FileSink::builder`]. Clones write to the same file.
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    writer: Arc<FileWriter>,
+}
+
+impl FileSink {
+    /// Create a builder for a file sink writing to `path`, see
+    /// [`log_init`](crate::log_init) for the supported format specifiers
+    pub fn builder(path: &str) -> FileSinkBuilder {
+        FileSinkBuilder::new(path)
+    }
+
+    /// Path of the file currently written to
+    pub fn path(&self) -> String {
+        self.writer.path()
+    }
+}
+
+impl Sink for FileSink {
+    fn write(&self, record: &Record, format: &Format) -> io::Result<()> {
+        self.writer.write_record(record, format);
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.writer.flush();
+        Ok(())
+    }
+
+    fn close(&self) -> io::Result<()> {
+        self.writer.shutdown();
+        Ok(())
+    }
+}
+
+/// Builder for [`FileSink`]
+#[derive(Debug, Clone)]
+pub struct FileSinkBuilder {
+    pub(crate) config: FileConfig,
+}
+
+impl FileSinkBuilder {
+    fn new(path: &str) -> Self {
+        Self {
+            config: FileConfig {
+                pattern: path.to_string(),
+                flush_policy: FlushPolicy::default(),
+                flush_level: ERROR,
+                max_size: None,
+                max_files: 5,
+                rotation: Rotation::default(),
+                retention: None,
+                compression: Compression::default(),
+            },
+        }
+    }
+
+    /// When buffered records are written to the file,
+    /// flushes after every record by default
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.config.flush_policy = match policy {
+            // The flush thread would never sleep
+            FlushPolicy::Interval(interval) if interval.is_zero() => FlushPolicy::Always,
+            policy => policy,
+        };
+        self
+    }
+
+    /// Records with this level or a more severe one are always
+    /// flushed immediately, `ERROR` by default
+    pub fn flush_level(mut self, level: u32) -> Self {
+        self.config.flush_level = level;
+        self
+    }
+
+    /// Rotate the file when it would grow past `bytes`
+    ///
+    /// If the path pattern expands to a new name at that point, logging
+    /// continues in the new file. Otherwise the file is renamed to
+    /// `name.1`, older files are shifted to `name.2`, `name.3`, ...
+    /// and a new file is started.
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.config.max_size = Some(bytes);
+        self
+    }
+
+    /// Number of rotated files kept next to the active one, 5 by default
+    pub fn max_files(mut self, count: usize) -> Self {
+        self.config.max_files = count;
+        self
+    }
+
+    /// When the path pattern is expanded again to switch to a new file
+    ///
+    /// With `app_%F.log` and [`Rotation::Daily`], logging continues in a
+    /// new file every midnight. Both files get a line pointing to the
+    /// other one.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.config.rotation = rotation;
+        self
+    }
+
+    /// Remove or archive old log files produced by the path pattern
+    ///
+    /// Applied when the sink is built and after every rotation.
+    pub fn retention(mut self, retention: Retention) -> Self {
+        self.config.retention = Some(retention);
+        self
+    }
+
+    /// Compress rotated files on a background thread
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    /// Build the sink, expanding the path pattern with the current time
+    pub fn build(self) -> FileSink {
+        FileSink {
+            writer: FileWriter::new(self.config),
+        }
+    }
+}
+
+/// Log file kept open with a buffered writer
+#[derive(Debug)]
+pub(crate) struct FileWriter {
+    config: FileConfig,
+    state: Arc<Mutex<State>>,
+    compressor: Compressor,
+}
+
+#[derive(Debug)]
+struct State {
+    path: String,
+    writer: Option<BufWriter<File>>,
+    size: u64,
+    pending: usize,
+    last_flush: Instant,
+    /// Time at which the path pattern is expanded again
+    next_rotation: Option<DateTime<Local>>,
+    last_check: Instant,
+    /// Number of size rotations, names the files waiting to be shifted
+    rotations: u64,
+}
+
+impl FileWriter {
+    pub(crate) fn new(config: FileConfig) -> Arc<Self> {
+        let writer = Arc::new(Self {
+            state: Arc::new(Mutex::new(State {
+                path: expand_path(&config.pattern),
+                writer: None,
+                size: 0,
+                pending: 0,
+                last_flush: Instant::now(),
+                next_rotation: next_rotation(config.rotation, Local::now()),
+                last_check: Instant::now(),
+                rotations: 0,
+            })),
+            compressor: Compressor::new(config.compression),
+            config,
+        });
+        if let Some(retention) = &writer.config.retention {
+            retention.apply(&writer.config.pattern, &writer.lock().path);
+        }
+        if let FlushPolicy::Interval(interval) = writer.config.flush_policy {
+            spawn_flusher(Arc::downgrade(&writer), interval);
+        }
+        writer
+    }
+
+    /// Path of the file currently written to
+    pub(crate) fn path(&self) -> String {
+        self.lock().path.clone()
+    }
+
+    /// Write a record, `format` is also used for the rotation markers
+    pub(crate) fn write_record(&self, record: &Record, format: &Format) {
+        let line = format.format(record);
+        let mut state = self.lock();
+        if state.writer.is_none() && !open(&mut state) {
+            return;
+        }
+        if self.rotation_due(&mut state) {
+            let path = expand_path(&self.config.pattern);
+            if path != state.path {
+                self.switch(&mut state, path, format);
+            }
+        }
+        let len = line.len() as u64 + 1;
+        if let Some(max_size) = self.config.max_size {
+            if state.size > 0 && state.size + len > max_size {
+                self.rotate(&mut state, format);
+                if state.writer.is_none() && !open(&mut state) {
+                    return;
+                }
+            }
+        }
+        let Some(writer) = state.writer.as_mut() else {
+            return;
+        };
+        if let Err(e) = writer
+            .write_all(line.as_bytes())
+            .and_then(|_| writer.write_all(b"\n"))
+        {
+            eprintln!("Logger: Failed to write to file: {e}");
+            return;
+        }
+        state.size += len;
+        state.pending += 1;
+
+        let flush = match self.config.flush_policy {
+            _ if record.level <= self.config.flush_level => true,
+            FlushPolicy::Always => true,
+            FlushPolicy::Records(n) => state.pending >= n,
+            FlushPolicy::Interval(interval) => state.last_flush.elapsed() >= interval,
+        };
+        if flush {
+            flush_state(&mut state);
+        }
+    }
+
+    pub(crate) fn flush(&self) {
+        flush_state(&mut self.lock());
+    }
+
+    /// Flush and wait until rotated files are compressed
+    pub(crate) fn shutdown(&self) {
+        self.flush();
+        self.compressor.wait();
+    }
+
+    fn flush_if_due(&self, interval: Duration) {
+        let mut state = self.lock();
+        if state.pending > 0 && state.last_flush.elapsed() >= interval {
+            flush_state(&mut state);
+        }
+    }
+
+    /// Close the current file and continue in a new one
+    ///
+    /// If the path pattern expands to a new name, the new file is used.
+    /// Otherwise the current file is renamed to `name.1`, shifting older
+    /// files to `name.2`, `name.3`, ... and removing the ones past
+    /// `max_files`.
+    fn rotate(&self, state: &mut State, format: &Format) {
+        let path = expand_path(&self.config.pattern);
+        if path != state.path {
+            self.switch(state, path, format);
+            return;
+        }
+
+        flush_state(state);
+        state.writer = None;
+        state.size = 0;
+        let max_files = self.config.max_files;
+        let result = if max_files == 0 {
+            std::fs::remove_file(&state.path)
+        } else if self.compressor.enabled() {
+            // `name.1` may still be compressed, the file waits under a
+            // temporary name until the compression thread shifts the files
+            state.rotations += 1;
+            let rotated = format!("{}.{}.rotated", state.path, state.rotations);
+            std::fs::rename(&state.path, &rotated).map(|_| {
+                let path = state.path.clone();
+                self.compressor.run(move |compression| {
+                    shift(&path, max_files, compression.extension());
+                    let first = format!("{path}.1");
+                    match std::fs::rename(&rotated, &first) {
+                        Ok(_) => compress::compress(compression, &first),
+                        Err(e) => eprintln!("Logger: Failed to rotate file: {e}"),
+                    }
+                });
+            })
+        } else {
+            shift(&state.path, max_files, "");
+            std::fs::rename(&state.path, format!("{}.1", state.path))
+        };
+        if let Err(e) = result {
+            eprintln!("Logger: Failed to rotate file: {e}");
+        }
+        self.apply_retention(state);
+    }
+
+    /// Continue logging in the file at `path` and compress the old one
+    fn switch(&self, state: &mut State, path: String, format: &Format) {
+        let old = state.path.clone();
+        if switch(state, path, format) {
+            self.compressor.compress(&old);
+            self.apply_retention(state);
+        }
+    }
+
+    /// Apply the retention policy, after the queued files are compressed
+    /// if compression is enabled
+    fn apply_retention(&self, state: &State) {
+        let Some(retention) = &self.config.retention else {
+            return;
+        };
+        if !self.compressor.enabled() {
+            retention.apply(&self.config.pattern, &state.path);
+            return;
+        }
+        let retention = retention.clone();
+        let pattern = self.config.pattern.clone();
+        let state = self.state.clone();
+        self.compressor.run(move |_| {
+            let state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            retention.apply(&pattern, &state.path);
+        });
+    }
+
+    /// Returns `true` if the path pattern should be expanded again
+    fn rotation_due(&self, state: &mut State) -> bool {
+        match self.config.rotation {
+            Rotation::Never => false,
+            Rotation::OnChange => {
+                if state.last_check.elapsed() < Duration::from_secs(1) {
+                    return false;
+                }
+                state.last_check = Instant::now();
+                true
+            }
+            Rotation::Hourly | Rotation::Daily => {
+                let now = Local::now();
+                match state.next_rotation {
+                    Some(next) if now >= next => {
+                        state.next_rotation = next_rotation(self.config.rotation, now);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for FileWriter {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Shift `path.1`, `path.2`, ... (optionally followed by `ext`) to the
+/// next number, removing the ones past `max_files`
+fn shift(path: &str, max_files: usize, ext: &str) {
+    let numbered = |n: usize| format!("{path}.{n}");
+    let _ = std::fs::remove_file(numbered(max_files));
+    let _ = std::fs::remove_file(numbered(max_files) + ext);
+    for n in (1..max_files).rev() {
+        let _ = std::fs::rename(numbered(n), numbered(n + 1));
+        let _ = std::fs::rename(numbered(n) + ext, numbered(n + 1) + ext);
+    }
+}
+
+/// Expand a path pattern with the current time,
+/// falls back to [`DEFAULT_PATH`] if the pattern is invalid
+pub(crate) fn expand_path(pattern: &str) -> String {
+    match current_time_fmt(pattern) {
+        p if p.is_empty() => DEFAULT_PATH.to_string(),
+        p => p,
+    }
+}
+
+/// Start of the next hour or day after `now`
+fn next_rotation(rotation: Rotation, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let next = match rotation {
+        Rotation::Hourly => {
+            now.date_naive().and_hms_opt(now.hour(), 0, 0)? + ChronoDuration::hours(1)
+        }
+        Rotation::Daily => now.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?,
+        Rotation::Never | Rotation::OnChange => return None,
+    };
+    // The start of the hour can be skipped by a DST change
+    next.and_local_timezone(Local)
+        .earliest()
+        .or_else(|| Some(now + ChronoDuration::hours(1)))
+}
+
+/// Continue logging in the file at `path`
+///
+/// The old file stays open until the new one was opened, both get a line
+/// pointing to the other one. Returns `false` if the new file could not
+/// be opened.
+fn switch(state: &mut State, path: String, format: &Format) -> bool {
+    let mut file = match std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&path)
+    {
+        Ok(f) => BufWriter::new(f),
+        Err(e) => {
+            eprintln!("Logger: Failed to open file: {e}");
+            return false;
+        }
+    };
+    if let Some(writer) = state.writer.as_mut() {
+        let marker = Record::new(INFO, &format!("Log continues in {path}"));
+        let _ = writeln!(writer, "{}", format.format(&marker));
+    }
+    flush_state(state);
+
+    let marker = Record::new(INFO, &format!("Log continued from {}", state.path));
+    let marker = format.format(&marker) + "\n";
+    if let Err(e) = file.write_all(marker.as_bytes()) {
+        eprintln!("Logger: Failed to write to file: {e}");
+    }
+    state.size = file.get_ref().metadata().map(|m| m.len()).unwrap_or(0) + marker.len() as u64;
+    state.writer = Some(file);
+    state.path = path;
+    true
+}
+
+/// Open the file at `state.path` for appending, returns `false` on failure
+fn open(state: &mut State) -> bool {
+    match std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&state.path)
+    {
+        Ok(f) => {
+            state.size = f.metadata().map(|m| m.len()).unwrap_or(0);
+            state.writer = Some(BufWriter::new(f));
+            true
+        }
+        Err(e) => {
+            eprintln!("Logger: Failed to open file: {e}");
+            false
+        }
+    }
+}
+
+fn flush_state(state: &mut State) {
+    if let Some(writer) = state.writer.as_mut() {
+        if let Err(e) = writer.flush() {
+            eprintln!("Logger: Failed to flush file: {e}");
+        }
+    }
+    state.pending = 0;
+    state.last_flush = Instant::now();
+}
+
+/// Flush records left in the buffer when no new records arrive
+fn spawn_flusher(writer: Weak<FileWriter>, interval: Duration) {
+    let result = std::thread::Builder::new()
+        .name("se-logger flush".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(interval);
+            match writer.upgrade() {
+                Some(writer) => writer.flush_if_due(interval),
+                None => break,
+            }
+        });
+    if let Err(e) = result {
+        eprintln!("Logger: Failed to start flush thread: {e}");
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{level_to_string, Level, ParseLevelError, INFO, TRACE};
+
+/// Environment variable read by [`Filter::from_env`]
+pub(crate) const LEVEL_VAR: &str = "SE_LOG_LEVEL";
+
+/// Per-target log levels
+///
+/// A filter is a comma-separated list of directives:
+///
+/// | Directive | Effect |
+/// |-----------|--------|
+/// | `debug` | Default level of targets without a directive |
+/// | `myapp=debug` | Level of `myapp` and its submodules |
+/// | `myapp` | Everything from `myapp`, same as `myapp=trace` |
+/// | `hyper=off` | Nothing from `hyper` |
+///
+/// Levels are names (`trace`, `debug`, `info`, `warn`, `warning`, `error`,
+/// `fatal`, case-insensitive) or numbers `0`..`5`. The target of a record
+/// is matched against the directive with the longest matching prefix,
+/// a prefix only matches whole path segments: `myapp` matches `myapp` and
+/// `myapp::db`, but not `myapp_cli`. Targets without a directive use the
+/// default level, `INFO` if none is given.
+/// ```
+/// use se_logger::*;
+///
+/// let filter = Filter::parse("myapp=debug,myapp::db=trace,hyper=warning").unwrap();
+/// assert!(filter.enabled("myapp::db::pool", TRACE));
+/// assert!(!filter.enabled("myapp::http", TRACE));
+/// assert!(!filter.enabled("hyper::client", INFO));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    /// Level of targets without a directive, `None` if they are disabled
+    default: Option<u32>,
+    /// Sorted by descending target length, so the first match is the longest
+    directives: Vec<Directive>,
+    /// Most verbose level of any target
+    max_level: Option<u32>,
+    /// Least verbose level of any target, records at or above it are
+    /// enabled without matching the target
+    min_level: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Directive {
+    target: String,
+    level: Option<u32>,
+}
+
+/// Error returned when parsing an invalid [`Filter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError {
+    /// Byte offset of the error in the filter
+    pub position: usize,
+    /// Description of the error
+    pub message: String,
+}
+
+impl Filter {
+    /// Create a filter with the same level for every target
+    pub fn new(level: u32) -> Self {
+        Self::from_parts(Some(level.min(TRACE)), Vec::new())
+    }
+
+    /// Parse a filter, see [`Filter`] for the syntax
+    pub fn parse(filter: &str) -> Result<Self, FilterError> {
+        let mut default = Some(INFO);
+        let mut directives: Vec<Directive> = Vec::new();
+        let mut offset = 0;
+        for part in filter.split(',') {
+            let position = offset + part.len() - part.trim_start().len();
+            offset += part.len() + 1;
+            let part = part.trim();
+            if part.is_empty() {
+                continue;
+            }
+            let (target, level) = match part.split_once('=') {
+                Some((target, level)) => {
+                    let level_position = position + target.len() + 1;
+                    match parse_level(level.trim()) {
+                        Ok(level) => (target.trim(), level),
+                        Err(e) => return Err(FilterError::new(level_position, &e.to_string())),
+                    }
+                }
+                // A bare word is a level if it parses as one, a target otherwise
+                None => match parse_level(part) {
+                    Ok(level) => {
+                        default = level;
+                        continue;
+                    }
+                    Err(_) => (part, Some(TRACE)),
+                },
+            };
+            if target.is_empty() {
+                return Err(FilterError::new(position, "empty target"));
+            }
+            if target.contains(char::is_whitespace) {
+                return Err(FilterError::new(
+                    position,
+                    &format!("invalid target `{target}`"),
+                ));
+            }
+            // Later directives override earlier ones for the same target
+            directives.retain(|d| d.target != target);
+            directives.push(Directive {
+                target: target.to_string(),
+                level,
+            });
+        }
+        Ok(Self::from_parts(default, directives))
+    }
+
+    /// Parse the filter in the `SE_LOG_LEVEL` environment variable,
+    /// `None` if it is not set
+    pub fn from_env() -> Option<Result<Self, FilterError>> {
+        let value = std::env::var(LEVEL_VAR).ok()?;
+        Some(Self::parse(&value))
+    }
+
+    /// Returns `true` if records from `target` with `level` pass the filter
+    pub fn enabled(&self, target: &str, level: u32) -> bool {
+        match (self.max_level, self.min_level) {
+            (None, _) => false,
+            (Some(max), _) if level > max => false,
+            (_, Some(min)) if level <= min => true,
+            _ => self.level(target).is_some_and(|l| level <= l),
+        }
+    }
+
+    /// Level of `target`, `None` if it is disabled
+    pub fn level(&self, target: &str) -> Option<u32> {
+        self.directives
+            .iter()
+            .find(|d| matches_target(&d.target, target))
+            .map_or(self.default, |d| d.level)
+    }
+
+    /// Most verbose level enabled for any target, `None` if every
+    /// target is disabled
+    pub fn max_level(&self) -> Option<u32> {
+        self.max_level
+    }
+
+    fn from_parts(default: Option<u32>, mut directives: Vec<Directive>) -> Self {
+        directives.sort_by_key(|d| std::cmp::Reverse(d.target.len()));
+        let levels = || std::iter::once(default).chain(directives.iter().map(|d| d.level));
+        let max_level = levels().flatten().max();
+        let min_level = levels().min().flatten();
+        Self {
+            default,
+            directives,
+            max_level,
+            min_level,
+        }
+    }
+}
+
+impl Default for Filter {
+    /// `INFO` for every target
+    fn default() -> Self {
+        Self::new(INFO)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = |level: Option<u32>| match level {
+            Some(level) => level_to_string(level).to_lowercase(),
+            None => "off".to_string(),
+        };
+        write!(f, "{}", level(self.default))?;
+        // Shortest first, so the output reads from general to specific
+        for directive in self.directives.iter().rev() {
+            write!(f, ",{}={}", directive.target, level(directive.level))?;
+        }
+        Ok(())
+    }
+}
+
+impl FilterError {
+    fn new(position: usize, message: &str) -> Self {
+        Self {
+            position,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid filter at {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// Parse a level, `Ok(None)` for `off`
+fn parse_level(s: &str) -> Result<Option<u32>, ParseLevelError> {
+    if s.eq_ignore_ascii_case("off") {
+        return Ok(None);
+    }
+    s.parse::<Level>().map(|level| Some(level.into()))
+}
+
+/// Returns `true` if `prefix` is `target` or one of its parent modules
+fn matches_target(prefix: &str, target: &str) -> bool {
+    match target.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt::Write;
+
+use crate::{level_to_string, Pattern, Record};
+
+/// Layout used to render a log line
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Format {
+    /// `[%T] [LEVEL] [thread] message key=value...`
+    #[default]
+    Bracket,
+    /// One JSON object per line:
+    ///
+    /// `{"timestamp":"2022-09-02T06:27:44.123+02:00","level":"INFO","thread":"main","target":"app","message":"..."}`
+    ///
+    /// Fields of the record are added as a `fields` object.
+    Json,
+    /// `key=value` pairs:
+    ///
+    /// `ts=2022-09-02T06:27:44.123+02:00 level=INFO thread=main target=app msg="..."`
+    ///
+    /// Fields of the record are appended as additional pairs.
+    Logfmt,
+    /// User-defined layout, see [`Pattern`]
+    Pattern(Pattern),
+}
+
+impl Format {
+    /// Render a record as a single line, without the line terminator
+    pub fn format(&self, record: &Record) -> String {
+        match self {
+            Format::Bracket => bracket(record),
+            Format::Json => json(record),
+            Format::Logfmt => logfmt(record),
+            Format::Pattern(pattern) => pattern.format(record),
+        }
+    }
+}
+
+fn bracket(record: &Record) -> String {
+    let mut line = format!(
+        "[{}] [{}] [{}] {}",
+        record.time.format("%T"),
+        level_to_string(record.level),
+        record.thread,
+        record.message
+    );
+    for (key, value) in &record.fields {
+        let _ = write!(line, " {key}={value}");
+    }
+    line
+}
+
+fn json(record: &Record) -> String {
+    let mut line = String::with_capacity(128 + record.message.len());
+    line.push_str("{\"timestamp\":");
+    json_string(&mut line, &record.time.format("%FT%T%.3f%:z").to_string());
+    line.push_str(",\"level\":");
+    json_string(&mut line, &level_to_string(record.level));
+    line.push_str(",\"thread\":");
+    json_string(&mut line, &record.thread);
+    line.push_str(",\"target\":");
+    json_string(&mut line, &record.target);
+    line.push_str(",\"message\":");
+    json_string(&mut line, &record.message);
+    if !record.fields.is_empty() {
+        line.push_str(",\"fields\":{");
+        for (i, (key, value)) in record.fields.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            json_string(&mut line, key);
+            line.push(':');
+            json_string(&mut line, value);
+        }
+        line.push('}');
+    }
+    line.push('}');
+    line
+}
+
+fn logfmt(record: &Record) -> String {
+    let mut line = String::with_capacity(128 + record.message.len());
+    let ts = record.time.format("%FT%T%.3f%:z").to_string();
+    let level = level_to_string(record.level);
+    let pairs = [
+        ("ts", ts.as_str()),
+        ("level", level.as_str()),
+        ("thread", record.thread.as_str()),
+        ("target", record.target.as_str()),
+        ("msg", record.message.as_str()),
+    ];
+    let fields = record.fields.iter().map(|(k, v)| (k.as_str(), v.as_str()));
+    for (i, (key, value)) in pairs.into_iter().chain(fields).enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        logfmt_key(&mut line, key);
+        line.push('=');
+        logfmt_value(&mut line, value);
+    }
+    line
+}
+
+/// Append `key`, replacing characters that would break the pair
+fn logfmt_key(out: &mut String, key: &str) {
+    if key.is_empty() {
+        out.push('_');
+    }
+    for c in key.chars() {
+        match c {
+            ' ' | '=' | '"' => out.push('_'),
+            c if c.is_control() => out.push('_'),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Append `value`, quoted if it is empty or contains spaces,
+/// `=`, quotes or control characters
+fn logfmt_value(out: &mut String, value: &str) {
+    let quote = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c.is_control());
+    if !quote {
+        out.push_str(value);
+        return;
+    }
+    out.push('"');
+    push_escaped(out, value);
+    out.push('"');
+}
+
+/// Append `s` as a quoted JSON string
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    push_escaped(out, s);
+    out.push('"');
+}
+
+/// Append `s` with quotes, backslashes and control characters escaped
+fn push_escaped(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::ffi::c_int;
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use crate::syslog::{app_name, severity};
+use crate::{Format, Record, Sink};
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Sink sending records to systemd-journald with its native protocol
+///
+/// Every record becomes a journal entry with the fields `PRIORITY`,
+/// `MESSAGE`, `SYSLOG_IDENTIFIER`, `THREAD_NAME`, `CODE_FILE`,
+/// `CODE_LINE` and `CODE_MODULE` if known, and the fields of the record
+/// with their names upper-cased, e.g. `user_id` as `USER_ID`. `format`
+/// renders `MESSAGE`, a pattern such as `{m}` keeps it free of the time
+/// and level the journal stores anyway.
+///
+/// Entries too large for a datagram are passed in a sealed memfd.
+/// ```
+/// use se_logger::*;
+/// use std::os::unix::net::UnixDatagram;
+///
+/// let path = std::env::temp_dir().join("se-logger-journald-doc.sock");
+/// let _ = std::fs::remove_file(&path);
+/// let listener = UnixDatagram::bind(&path).unwrap();
+///
+/// let sink = JournaldSink::new().socket(&path).syslog_identifier("myapp");
+/// let mut record = Record::new(WARNING, "disk almost full");
+/// record.fields.push(("free_bytes".to_string(), "1024".to_string()));
+/// sink.write(&record, &Format::Pattern(Pattern::parse("{m}").unwrap())).unwrap();
+///
+/// let mut buf = [0; 4096];
+/// let len = listener.recv(&mut buf).unwrap();
+/// let entry = String::from_utf8_lossy(&buf[..len]);
+/// assert!(entry.contains("PRIORITY=4\n"));
+/// assert!(entry.contains("MESSAGE=disk almost full\n"));
+/// assert!(entry.contains("FREE_BYTES=1024\n"));
+/// # let _ = std::fs::remove_file(&path);
+/// ```
+#[derive(Debug)]
+pub struct JournaldSink {
+    path: PathBuf,
+    identifier: String,
+    socket: Mutex<Option<UnixDatagram>>,
+}
+
+impl JournaldSink {
+    /// Create a sink sending to the journal socket of the system,
+    /// identified by the executable name
+    pub fn new() -> Self {
+        Self {
+            path: PathBuf::from(JOURNAL_SOCKET),
+            identifier: app_name(),
+            socket: Mutex::new(None),
+        }
+    }
+
+    /// Path of the journal socket, `/run/systemd/journal/socket` by default
+    pub fn socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Value of `SYSLOG_IDENTIFIER`, the executable name by default
+    pub fn syslog_identifier(mut self, identifier: &str) -> Self {
+        self.identifier = identifier.to_string();
+        self
+    }
+
+    /// Serialize a record as a journal entry
+    fn entry(&self, record: &Record, format: &Format) -> Vec<u8> {
+        let mut entry = Vec::with_capacity(256 + record.message.len());
+        push_field(&mut entry, "PRIORITY", &severity(record.level).to_string());
+        push_field(&mut entry, "MESSAGE", &format.format(record));
+        push_field(&mut entry, "SYSLOG_IDENTIFIER", &self.identifier);
+        push_field(&mut entry, "THREAD_NAME", &record.thread);
+        if let Some(file) = &record.file {
+            push_field(&mut entry, "CODE_FILE", file);
+        }
+        if let Some(line) = record.line {
+            push_field(&mut entry, "CODE_LINE", &line.to_string());
+        }
+        if let Some(module) = &record.module_path {
+            push_field(&mut entry, "CODE_MODULE", module);
+        }
+        for (key, value) in &record.fields {
+            if let Some(name) = field_name(key) {
+                push_field(&mut entry, &name, value);
+            }
+        }
+        entry
+    }
+
+    fn send(&self, socket: &mut Option<UnixDatagram>, entry: &[u8]) -> io::Result<()> {
+        if socket.is_none() {
+            let s = UnixDatagram::unbound()?;
+            s.connect(&self.path)?;
+            *socket = Some(s);
+        }
+        let Some(s) = socket.as_ref() else {
+            return Ok(());
+        };
+        match s.send(entry) {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EMSGSIZE | libc::ENOBUFS)) => {
+                send_memfd(s, entry)
+            }
+            Err(e) => {
+                *socket = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<UnixDatagram>> {
+        self.socket.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for JournaldSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sink for JournaldSink {
+    fn write(&self, record: &Record, format: &Format) -> io::Result<()> {
+        let entry = self.entry(record, format);
+        let mut socket = self.lock();
+        // journald restarts invalidate the connected socket, retry once
+        self.send(&mut socket, &entry)
+            .or_else(|_| self.send(&mut socket, &entry))
+    }
+
+    fn close(&self) -> io::Result<()> {
+        *self.lock() = None;
+        Ok(())
+    }
+}
+
+/// Append `NAME=value\n`, or the length-prefixed form if `value`
+/// contains a newline
+fn push_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+/// Journal field name for a record field: upper-case ASCII letters,
+/// digits and underscores, not starting with a digit or an underscore
+fn field_name(key: &str) -> Option<String> {
+    let name: String = key
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .take(64)
+        .collect();
+    let name = name.trim_start_matches(|c: char| c == '_' || c.is_ascii_digit());
+    match name {
+        "" => None,
+        name => Some(name.to_string()),
+    }
+}
+
+/// Pass `entry` in a sealed memfd, as journald expects for entries that
+/// do not fit in a datagram
+fn send_memfd(socket: &UnixDatagram, entry: &[u8]) -> io::Result<()> {
+    // SAFETY: the name is a valid C string, the flags are valid
+    let fd = unsafe {
+        libc::memfd_create(
+            c"se-logger-journal".as_ptr(),
+            libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` was just created and is owned by nothing else,
+    // the file closes it when dropped
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(entry)?;
+    let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+    // SAFETY: `fd` is a valid memfd created with sealing allowed
+    if unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: CMSG_SPACE only computes a size
+    let space = unsafe { libc::CMSG_SPACE(std::mem::size_of::<c_int>() as u32) } as usize;
+    // u64 elements keep the buffer aligned for `cmsghdr`
+    let mut control = vec![0u64; space.div_ceil(8)];
+    // SAFETY: an all-zero `msghdr` is a valid empty message
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    // SAFETY: the control buffer holds one header with space for one fd,
+    // the socket is connected so no address is needed
+    let sent = unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<c_int>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<c_int>(), file.as_raw_fd());
+        libc::sendmsg(socket.as_raw_fd(), &msg, 0)
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+use tracing_core::field::{Field, Visit};
+use tracing_core::span::{Attributes, Id, Record as SpanRecord};
+use tracing_core::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::{global, Logger, Record, DEBUG, ERROR, INFO, TRACE, WARNING};
+
+/// [`tracing_subscriber::Layer`] that writes events with se-logger
+///
+/// Events are rendered in the same format as the free functions and
+/// filtered with the logger level. The names and fields of the spans an
+/// event is in are prepended to its message:
+/// ```text
+/// [19:35:33] [INFO] [main] request{id=7}:db: query finished rows=3
+/// ```
+pub struct SeLoggerLayer {
+    logger: Option<Arc<Logger>>,
+}
+
+impl SeLoggerLayer {
+    /// Create a layer writing to the process-global logger
+    pub fn new() -> Self {
+        Self { logger: None }
+    }
+
+    /// Create a layer writing to `logger`
+    pub fn with_logger(logger: Arc<Logger>) -> Self {
+        Self {
+            logger: Some(logger),
+        }
+    }
+}
+
+impl Default for SeLoggerLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formatted fields of a span, stored in the span extensions
+struct SpanFields(String);
+
+impl<S> tracing_subscriber::Layer<S> for SeLoggerLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut()
+            .insert(SpanFields(join_fields(&visitor.fields)));
+    }
+
+    fn on_record(&self, id: &Id, values: &SpanRecord<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        let mut extensions = span.extensions_mut();
+        match extensions.get_mut::<SpanFields>() {
+            Some(SpanFields(s)) => {
+                if !s.is_empty() && !visitor.fields.is_empty() {
+                    s.push(' ');
+                }
+                s.push_str(&join_fields(&visitor.fields));
+            }
+            None => extensions.insert(SpanFields(join_fields(&visitor.fields))),
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let logger = match &self.logger {
+            Some(logger) => logger.clone(),
+            None => global(),
+        };
+        let metadata = event.metadata();
+        let level = from_tracing_level(metadata.level());
+        if !logger.enabled_for(metadata.target(), level) {
+            return;
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut message = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                message.push_str(span.name());
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    if !fields.is_empty() {
+                        let _ = write!(message, "{{{fields}}}");
+                    }
+                }
+                message.push(':');
+            }
+            message.push(' ');
+        }
+        message.push_str(&visitor.message);
+
+        let mut record = Record::new(level, &message);
+        record.target = metadata.target().to_string();
+        record.module_path = metadata.module_path().map(str::to_string);
+        record.file = metadata.file().map(str::to_string);
+        record.line = metadata.line();
+        record.fields = visitor.fields;
+        logger.log_record(&record);
+    }
+}
+
+/// Collects the `message` field and the remaining fields of an event or span
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .push((field.name().to_string(), value.to_string()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields
+                .push((field.name().to_string(), format!("{value:?}")));
+        }
+    }
+}
+
+fn join_fields(fields: &[(String, String)]) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn from_tracing_level(level: &tracing_core::Level) -> u32 {
+    match *level {
+        tracing_core::Level::ERROR => ERROR,
+        tracing_core::Level::WARN => WARNING,
+        tracing_core::Level::INFO => INFO,
+        tracing_core::Level::DEBUG => DEBUG,
+        _ => TRACE,
+    }
+}
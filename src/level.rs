@@ -0,0 +1,131 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{DEBUG, ERROR, FATAL, INFO, TRACE, WARNING};
+
+/// Log level
+///
+/// Levels are ordered by verbosity like the numeric constants they
+/// convert to and from: `Fatal` < `Error` < ... < `Trace`.
+/// ```
+/// use se_logger::*;
+///
+/// let level: Level = "warn".parse().unwrap();
+/// assert_eq!(level, Level::Warning);
+/// assert_eq!(u32::from(level), WARNING);
+/// assert_eq!(Level::try_from(DEBUG), Ok(Level::Debug));
+/// assert!(Level::Trace > Level::Info);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u32)]
+pub enum Level {
+    Fatal = FATAL,
+    Error = ERROR,
+    Warning = WARNING,
+    Info = INFO,
+    Debug = DEBUG,
+    Trace = TRACE,
+}
+
+/// Error returned when parsing or converting an invalid [`Level`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLevelError {
+    value: String,
+}
+
+impl Level {
+    /// All levels, from the least to the most verbose
+    pub const ALL: [Level; 6] = [
+        Level::Fatal,
+        Level::Error,
+        Level::Warning,
+        Level::Info,
+        Level::Debug,
+        Level::Trace,
+    ];
+
+    /// Upper-case name of the level, as written in log lines
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Fatal => "FATAL",
+            Level::Error => "ERROR",
+            Level::Warning => "WARNING",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    /// Parse a case-insensitive name (`trace`, `debug`, `info`, `warn`,
+    /// `warning`, `error`, `fatal`) or a number `0`..`5`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let level = match s.to_ascii_lowercase().as_str() {
+            "trace" => Level::Trace,
+            "debug" => Level::Debug,
+            "info" => Level::Info,
+            "warn" | "warning" => Level::Warning,
+            "error" => Level::Error,
+            "fatal" => Level::Fatal,
+            n => {
+                return n
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(|n| Level::try_from(n).ok())
+                    .ok_or_else(|| ParseLevelError::new(s))
+            }
+        };
+        Ok(level)
+    }
+}
+
+impl TryFrom<u32> for Level {
+    type Error = ParseLevelError;
+
+    fn try_from(level: u32) -> Result<Self, ParseLevelError> {
+        match level {
+            TRACE => Ok(Level::Trace),
+            DEBUG => Ok(Level::Debug),
+            INFO => Ok(Level::Info),
+            WARNING => Ok(Level::Warning),
+            ERROR => Ok(Level::Error),
+            FATAL => Ok(Level::Fatal),
+            n => Err(ParseLevelError::new(&n.to_string())),
+        }
+    }
+}
+
+impl From<Level> for u32 {
+    fn from(level: Level) -> Self {
+        level as u32
+    }
+}
+
+impl ParseLevelError {
+    fn new(value: &str) -> Self {
+        Self {
+            value: value.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid level `{}`, expected trace, debug, info, warning, error, fatal or 0..5",
+            self.value
+        )
+    }
+}
+
+impl std::error::Error for ParseLevelError {}
@@ -1,10 +1,10 @@
 //! # se-logger
 //! Simple customizable logging crate.
-//! 
+//!
 //! # Getting started
 //! ```
 //! use se_logger::*;
-//! 
+//!
 //! fn main() {
 //!     // Initialize the logger with the log file path and log level
 //!     // File path will be test_19_35_33.log (for example)
@@ -16,12 +16,121 @@
 //! }
 //! ```
 //! Output:
-//! ```
+//! ```text
 //! [19:35:33] [INFO] [main] This is an info message
 //! [19:35:33] [ERROR] [main] This is an error message
 //! ```
+//!
+//! # Macros
+//! The macros accept `format!` syntax, only format the message if its
+//! level is enabled and record the module, file and line of the call:
+//! ```no_run
+//! use se_logger::*;
+//!
+//! let user = "alice";
+//! info!("User {user} logged in");
+//! warning!("{} retries left", 3);
+//! ```
+//!
+//! # Logger instances
+//! [`log_init`] is a shortcut for building a [`Logger`] and installing it
+//! as the process-global logger. Use [`Logger::builder`] for more settings,
+//! or to keep independent loggers:
+//! ```no_run
+//! use se_logger::*;
+//!
+//! let logger = Logger::builder()
+//!     .path("db_%F.log")
+//!     .level(DEBUG)
+//!     .console(false)
+//!     .build();
+//! logger.debug("Only written to the database log");
+//! ```
+//!
+//! # Filters
+//! A [`Filter`] sets levels per module path or target. With
+//! [`LoggerBuilder::env_filter`] it is read from `SE_LOG_LEVEL`:
+//! ```text
+//! SE_LOG_LEVEL=info,myapp::db=trace,hyper=warning ./myapp
+//! ```
+//!
+//! # Panics
+//! [`install_panic_hook`] logs panics of any thread at `FATAL`, with a
+//! backtrace, before the default panic message is printed.
+//!
+//! # Cargo features
+//! - `config` - Load settings from a TOML file with `Config` and reload
+//!   them when the file changes with `ConfigWatcher`
+//! - `gzip` - Compress rotated log files with gzip
+//! - `journald` - Send records to systemd-journald with `JournaldSink`,
+//!   Linux only
+//! - `log` - Forward records from the [`log`](https://docs.rs/log) crate
+//!   with `init_log_facade`
+//! - `tls` - Encrypt the stream of a `NetworkSink` with rustls
+//! - `tracing` - Write [`tracing`](https://docs.rs/tracing) events with
+//!   the `SeLoggerLayer` subscriber layer
+//! - `zstd` - Compress rotated log files with Zstandard
+
+use std::sync::{Arc, PoisonError, RwLock};
+
+mod color;
+mod compress;
+#[cfg(feature = "config")]
+mod config;
+mod console;
+#[cfg(feature = "log")]
+mod facade;
+mod file;
+mod filter;
+mod format;
+#[cfg(all(feature = "journald", target_os = "linux"))]
+mod journald;
+#[cfg(feature = "tracing")]
+mod layer;
+mod level;
+mod logger;
+mod macros;
+mod network;
+mod panic;
+mod pattern;
+mod queue;
+mod record;
+mod retention;
+mod ring;
+mod sink;
+mod syslog;
+#[cfg(feature = "config")]
+mod watch;
 
-use std::io::Write;
+pub use color::{Color, ColorChoice, Colors};
+pub use compress::Compression;
+#[cfg(feature = "config")]
+pub use config::{Config, ConfigError};
+pub use console::{Console, ConsoleSink};
+#[cfg(feature = "log")]
+pub use facade::init_log_facade;
+pub use file::{FileSink, FileSinkBuilder, FlushPolicy, Rotation};
+pub use filter::{Filter, FilterError};
+pub use format::Format;
+#[cfg(all(feature = "journald", target_os = "linux"))]
+pub use journald::JournaldSink;
+#[cfg(feature = "tracing")]
+pub use layer::SeLoggerLayer;
+pub use level::{Level, ParseLevelError};
+pub use logger::{Logger, LoggerBuilder};
+#[doc(hidden)]
+pub use macros::{__enabled, __log_args};
+pub use network::{Framing, NetworkProtocol, NetworkSink, NetworkSinkBuilder};
+pub use panic::install_panic_hook;
+pub use pattern::{Pattern, PatternError};
+pub use queue::OverflowPolicy;
+pub use record::Record;
+pub use retention::Retention;
+pub use ring::RingSink;
+pub use sink::Sink;
+pub use syslog::{Facility, SyslogBuilder, SyslogProtocol, SyslogSink, SyslogTransport};
+#[cfg(feature = "config")]
+pub use watch::ConfigWatcher;
 
 pub const TRACE: u32 = 5;
 pub const DEBUG: u32 = 4;
@@ -30,14 +139,13 @@ pub const WARNING: u32 = 2;
 pub const ERROR: u32 = 1;
 pub const FATAL: u32 = 0;
 
-const LOG_PATH_VAR: &str = "SE_LOG_PATH";
-const LOG_LEVEL_VAR: &str = "SE_LOG_LEVEL";
+static LOGGER: RwLock<Option<Arc<Logger>>> = RwLock::new(None);
 
 /// Initialize the logger with settings
 /// ### Arguments
 ///
 /// - `path` - Path to save log files to. Can be formated according to:
-/// 
+///
 /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html#specifiers>
 ///
 /// #### Example
@@ -50,116 +158,136 @@ const LOG_LEVEL_VAR: &str = "SE_LOG_LEVEL";
 ///     - `WARRNING` - 2
 ///     - `ERROR` - 1
 ///     - `FATAL` - 0
-/// 
+///
+///   [`Level`] converts between these values and level names.
+///
 /// ### Notes
 /// `%D`, `%x`, `%R`, `%T`, `%X`, `%r`, `%+` should not
 /// be used as they contain `/` or `:` which are disallowed in filenames.
-/// 
+///
 /// If a path is invalid, the default will be used: `unnamed.log`
 pub fn log_init(path: &str, level: u32) {
-    set_log_path(&current_time_fmt(path));
-    set_log_level(level);
+    Logger::builder().path(path).level(level).build().install();
 }
 
 /// Log a generic message
 pub fn log(message: &str) {
-    println!("{message}");
-    let mut f = match std::fs::OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(get_log_path())
-    {
-        Ok(f) => f,
-        Err(e) => {
-            println!("Logger: Failed to open file: {e}");
-            return;
-        }
-    };
-    match f.write((message.to_string() + "\n").as_bytes()) {
-        Ok(_) => {}
-        Err(e) => {
-            println!("Logger: Failed to write to file: {e}");
-            return;
-        }
-    }
+    global().log(message);
 }
 
 /// Log a trace message
 pub fn trace(message: &str) {
-    log_with_level(message, TRACE);
+    global().log_with_level(message, TRACE);
 }
 /// Log a debug message
 pub fn debug(message: &str) {
-    log_with_level(message, DEBUG);
+    global().log_with_level(message, DEBUG);
 }
 /// Log an info message
 pub fn info(message: &str) {
-    log_with_level(message, INFO);
+    global().log_with_level(message, INFO);
 }
 /// Log a warning message
 pub fn warning(message: &str) {
-    log_with_level(message, WARNING);
+    global().log_with_level(message, WARNING);
 }
 /// Log an error message
 pub fn error(message: &str) {
-    log_with_level(message, ERROR);
+    global().log_with_level(message, ERROR);
 }
 /// Log a fatal message
 pub fn fatal(message: &str) {
-    log_with_level(message, FATAL);
-}
-
-fn log_with_level(message: &str, level: u32) {
-    if get_log_level() >= level {
-        log(&format!(
-            "[{}] [{}] [{}] {}",
-            current_time_fmt("%T"),
-            level_to_string(level),
-            match std::thread::current().name() {
-                Some(s) => s,
-                None => "unnamed thread",
-            },
-            message
-        ))
-    }
+    global().log_with_level(message, FATAL);
 }
 
-fn get_log_level() -> u32 {
-    match std::env::var(LOG_LEVEL_VAR) {
-        Ok(s) => match s.parse::<u32>() {
-            Ok(v) => v,
-            Err(_) => INFO,
-        },
-        Err(_) => INFO,
-    }
+/// Set the level of the console and the file of the process-global
+/// logger, see [`Logger::set_level`]
+pub fn set_level(level: u32) {
+    let logger = global();
+    logger.set_level(level);
+    sync_max_level(&logger);
 }
-fn set_log_level(level: u32) {
-    if level >= FATAL && level <= TRACE {
-        std::env::set_var(LOG_LEVEL_VAR, level.to_string());
+
+/// Write buffered records of the process-global logger to its file
+pub fn flush() {
+    global().flush();
+}
+
+/// Records held by the ring buffer of the process-global logger,
+/// see [`Logger::dump_ring`]
+pub fn dump_ring() -> Vec<Record> {
+    global().dump_ring()
+}
+
+/// Write the records held by the ring buffer of the process-global
+/// logger to the file at `path`, see [`Logger::dump_ring_to`]
+pub fn dump_ring_to(path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    global().dump_ring_to(path)
+}
+
+/// Flush and uninstall the process-global logger
+///
+/// Call before exiting the process, the global logger is never dropped
+/// otherwise. Logging after a shutdown installs a logger with the default
+/// settings.
+pub fn shutdown() {
+    let logger = LOGGER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(logger) = logger {
+        logger.flush();
     }
 }
-fn get_log_path() -> String {
-    match std::env::var(LOG_PATH_VAR) {
-        Ok(s) => s,
-        Err(_) => "unnamed.log".to_string(),
+
+/// Returns the process-global logger.
+/// If none was installed, a logger with the default settings and the
+/// filter in `SE_LOG_LEVEL` is installed.
+fn global() -> Arc<Logger> {
+    if let Some(logger) = LOGGER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+    {
+        return logger.clone();
     }
+    LOGGER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert_with(|| Arc::new(Logger::builder().env_filter().build()))
+        .clone()
+}
+fn set_global(logger: Logger) {
+    replace_global(logger);
+}
+/// Replace the process-global logger, returns the previous one
+fn replace_global(logger: Logger) -> Option<Arc<Logger>> {
+    sync_max_level(&logger);
+    LOGGER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .replace(Arc::new(logger))
 }
-fn set_log_path(path: &str) {
-    std::env::set_var(LOG_PATH_VAR, path);
+/// Keep the level of the `log` crate in sync with `logger`
+fn sync_max_level(_logger: &Logger) {
+    #[cfg(feature = "log")]
+    facade::update_max_level(_logger.level());
 }
 
 fn level_to_string(level: u32) -> String {
-    match level {
-        TRACE => "TRACE",
-        DEBUG => "DEBUG",
-        INFO => "INFO",
-        WARNING => "WARNING",
-        ERROR => "ERROR",
-        FATAL => "FATAL",
-        _ => "",
-    }
-    .to_string()
+    Level::try_from(level)
+        .map(Level::as_str)
+        .unwrap_or("")
+        .to_string()
 }
+/// Format the current local time, returns an empty string
+/// if `fmt` contains invalid specifiers
 fn current_time_fmt(fmt: &str) -> String {
-    chrono::Local::now().format(fmt).to_string()
+    use std::fmt::Write;
+
+    let mut s = String::new();
+    match write!(s, "{}", chrono::Local::now().format(fmt)) {
+        Ok(_) => s,
+        Err(_) => String::new(),
+    }
 }
@@ -0,0 +1,652 @@
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+use crate::file::DEFAULT_PATH;
+use crate::panic::SinkGuard;
+use crate::queue::AsyncWriter;
+use crate::sink::{Builtin, SinkEntry};
+use crate::{
+    ColorChoice, Colors, Compression, Console, ConsoleSink, FileSink, FileSinkBuilder, Filter,
+    FlushPolicy, Format, OverflowPolicy, Pattern, Record, Retention, RingSink, Rotation, Sink,
+    FATAL, INFO, TRACE,
+};
+
+/// A logger instance
+///
+/// Loggers are created with [`Logger::builder`] and can either be used
+/// directly or installed as the process-global logger with
+/// [`Logger::install`], which is what the free functions
+/// ([`info`](crate::info()), [`error`](crate::error()), ...) write to.
+#[derive(Debug)]
+pub struct Logger {
+    /// Most verbose level of any sink, limited by the filter
+    level: AtomicU32,
+    filter: RwLock<Option<Filter>>,
+    output: Arc<Output>,
+    writer: Option<AsyncWriter>,
+    file: Option<FileSink>,
+    /// Ring buffer and the format used to dump it
+    ring: Option<(RingSink, Format)>,
+}
+
+impl Logger {
+    /// Create a builder with the default settings
+    pub fn builder() -> LoggerBuilder {
+        LoggerBuilder::new()
+    }
+
+    /// Install this logger as the process-global logger,
+    /// replacing the previous one
+    pub fn install(self) {
+        crate::set_global(self);
+    }
+
+    /// Path of the log file currently written to, after expanding the
+    /// pattern. `None` if the default file sink is disabled.
+    pub fn path(&self) -> Option<String> {
+        self.file.as_ref().map(FileSink::path)
+    }
+
+    /// Records held by the ring buffer, oldest first, see
+    /// [`LoggerBuilder::ring_buffer`]. Empty if there is none.
+    ///
+    /// For asynchronous loggers, waits until the queued records are written.
+    pub fn dump_ring(&self) -> Vec<Record> {
+        match &self.ring {
+            Some((ring, _)) => {
+                self.flush();
+                ring.records()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Write the records held by the ring buffer to the file at `path`,
+    /// rendered with the format of the log file
+    pub fn dump_ring_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        match &self.ring {
+            Some((ring, format)) => {
+                self.flush();
+                ring.dump(path, format)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "the logger has no ring buffer",
+            )),
+        }
+    }
+
+    /// Most verbose level written to any sink
+    pub fn level(&self) -> u32 {
+        self.level.load(Ordering::Relaxed)
+    }
+
+    /// Set the level of the console and the file and remove the filter
+    ///
+    /// Takes effect immediately for records logged afterwards, sinks added
+    /// with [`LoggerBuilder::sink`] keep their levels.
+    pub fn set_level(&self, level: u32) {
+        let level = level.min(TRACE);
+        self.set_levels(level, level, None);
+    }
+
+    /// Replace the per-target filter, `None` removes it
+    ///
+    /// The levels of the sinks still apply, see [`LoggerBuilder::filter`].
+    pub fn set_filter(&self, filter: Option<Filter>) {
+        *self.filter.write().unwrap_or_else(PoisonError::into_inner) = filter;
+        self.update_level();
+    }
+
+    /// Set the levels of the console and the file and the filter
+    pub(crate) fn set_levels(&self, console: u32, file: u32, filter: Option<Filter>) {
+        for entry in &self.output.sinks {
+            match entry.builtin {
+                Some(Builtin::Console) => entry.set_level(console),
+                Some(Builtin::File) => entry.set_level(file),
+                None => {}
+            }
+        }
+        self.set_filter(filter);
+    }
+
+    fn update_level(&self) {
+        let filter = self.filter.read().unwrap_or_else(PoisonError::into_inner);
+        self.level
+            .store(self.output.level(filter.as_ref()), Ordering::Relaxed);
+    }
+
+    /// Log a generic message
+    pub fn log(&self, message: &str) {
+        match &self.writer {
+            Some(writer) => writer.write_raw(message),
+            None => self.output.write_raw(message),
+        }
+    }
+
+    /// Write buffered records of all sinks
+    ///
+    /// For asynchronous loggers, waits until the queued records are written.
+    pub fn flush(&self) {
+        match &self.writer {
+            Some(writer) => writer.flush(),
+            None => self.output.flush(),
+        }
+    }
+
+    /// Write the queued records and stop the writer thread of an
+    /// asynchronous logger, records logged afterwards are written
+    /// synchronously. Closes all sinks, which flushes the log file and
+    /// waits until rotated files are compressed.
+    pub fn shutdown(&self) {
+        match &self.writer {
+            Some(writer) => writer.shutdown(),
+            None => self.output.shutdown(),
+        }
+    }
+
+    /// The registered sinks
+    #[cfg(feature = "config")]
+    pub(crate) fn sinks(&self) -> Vec<Arc<dyn Sink>> {
+        self.output
+            .sinks
+            .iter()
+            .map(|entry| entry.sink.clone())
+            .collect()
+    }
+
+    /// Shut down like [`Logger::shutdown`], the sinks in `shared` are
+    /// never closed by this logger
+    #[cfg(feature = "config")]
+    pub(crate) fn shutdown_keeping(&self, shared: Vec<Arc<dyn Sink>>) {
+        self.output
+            .kept
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(shared);
+        self.shutdown();
+    }
+
+    /// Returns `true` if messages with `level` would be logged
+    /// for at least one target
+    pub fn enabled(&self, level: u32) -> bool {
+        self.level() >= level
+    }
+
+    /// Returns `true` if messages from `target` with `level` would be logged
+    pub fn enabled_for(&self, target: &str, level: u32) -> bool {
+        self.enabled(level)
+            && self
+                .filter
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_ref()
+                .is_none_or(|filter| filter.enabled(target, level))
+    }
+
+    /// Log a message with the given level
+    pub fn log_with_level(&self, message: &str, level: u32) {
+        if self.enabled_for("", level) {
+            self.log_record(&Record::new(level, message));
+        }
+    }
+
+    /// Log a record, if its level is enabled for its target
+    pub fn log_record(&self, record: &Record) {
+        if self.enabled_for(&record.target, record.level) {
+            match &self.writer {
+                Some(writer) => writer.write_record(record.clone()),
+                None => self.output.write_record(record),
+            }
+        }
+    }
+
+    /// Log a trace message
+    pub fn trace(&self, message: &str) {
+        self.log_with_level(message, crate::TRACE);
+    }
+    /// Log a debug message
+    pub fn debug(&self, message: &str) {
+        self.log_with_level(message, crate::DEBUG);
+    }
+    /// Log an info message
+    pub fn info(&self, message: &str) {
+        self.log_with_level(message, crate::INFO);
+    }
+    /// Log a warning message
+    pub fn warning(&self, message: &str) {
+        self.log_with_level(message, crate::WARNING);
+    }
+    /// Log an error message
+    pub fn error(&self, message: &str) {
+        self.log_with_level(message, crate::ERROR);
+    }
+    /// Log a fatal message
+    pub fn fatal(&self, message: &str) {
+        self.log_with_level(message, crate::FATAL);
+    }
+}
+
+/// Writes records to the registered sinks
+pub(crate) struct Output {
+    sinks: Vec<SinkEntry>,
+    /// Format of messages logged without a level
+    raw_format: Format,
+    /// Sinks used by another logger, not closed by this one
+    kept: Mutex<Vec<Arc<dyn Sink>>>,
+}
+
+impl std::fmt::Debug for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Output")
+            .field("sinks", &self.sinks)
+            .field("raw_format", &self.raw_format)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Output {
+    /// Most verbose level of any sink, limited by `filter`
+    fn level(&self, filter: Option<&Filter>) -> u32 {
+        let level = self
+            .sinks
+            .iter()
+            .map(SinkEntry::level)
+            .max()
+            .unwrap_or(FATAL);
+        match filter {
+            Some(filter) => level.min(filter.max_level().unwrap_or(FATAL)),
+            None => level,
+        }
+    }
+
+    pub(crate) fn write_record(&self, record: &Record) {
+        let _guard = SinkGuard::enter();
+        for entry in &self.sinks {
+            if record.level <= entry.level() {
+                report(entry.sink.write(record, &entry.format));
+            }
+        }
+    }
+
+    /// Write a message without a level to every sink, ignoring their levels
+    pub(crate) fn write_raw(&self, message: &str) {
+        let record = Record::new(INFO, message);
+        let _guard = SinkGuard::enter();
+        for entry in &self.sinks {
+            report(entry.sink.write(&record, &self.raw_format));
+        }
+    }
+
+    pub(crate) fn flush(&self) {
+        let _guard = SinkGuard::enter();
+        for entry in &self.sinks {
+            report(entry.sink.flush());
+        }
+    }
+
+    pub(crate) fn shutdown(&self) {
+        let kept = self.kept.lock().unwrap_or_else(PoisonError::into_inner);
+        let _guard = SinkGuard::enter();
+        for entry in &self.sinks {
+            if !kept.iter().any(|sink| Arc::ptr_eq(sink, &entry.sink)) {
+                report(entry.sink.close());
+            }
+        }
+    }
+}
+
+fn report(result: std::io::Result<()>) {
+    if let Err(e) = result {
+        eprintln!("Logger: Sink error: {e}");
+    }
+}
+
+/// Builder for [`Logger`]
+///
+/// The builder configures a console sink and a file sink,
+/// more sinks can be added with [`LoggerBuilder::sink`].
+#[derive(Debug, Clone)]
+pub struct LoggerBuilder {
+    console_level: Option<u32>,
+    file_level: Option<u32>,
+    filter: Option<Filter>,
+    console: Console,
+    console_format: Format,
+    file_format: Format,
+    color: ColorChoice,
+    colors: Colors,
+    file_enabled: bool,
+    file: FileSinkBuilder,
+    sinks: Vec<SinkEntry>,
+    ring: Option<RingSink>,
+    queue: Option<(usize, OverflowPolicy)>,
+}
+
+impl LoggerBuilder {
+    /// Create a builder with the default settings:
+    /// `unnamed.log`, `INFO`, console output enabled and the bracket format
+    pub fn new() -> Self {
+        Self {
+            console_level: None,
+            file_level: None,
+            filter: None,
+            console: Console::default(),
+            console_format: Format::default(),
+            file_format: Format::default(),
+            color: ColorChoice::default(),
+            colors: Colors::default(),
+            file_enabled: true,
+            file: FileSink::builder(DEFAULT_PATH),
+            sinks: Vec::new(),
+            ring: None,
+            queue: None,
+        }
+    }
+
+    /// Path to save log files to, see [`log_init`](crate::log_init)
+    /// for the supported format specifiers
+    pub fn path(mut self, path: &str) -> Self {
+        self.file.config.pattern = path.to_string();
+        self
+    }
+
+    /// Log level of the console and the file, values above `TRACE` are ignored
+    pub fn level(self, level: u32) -> Self {
+        self.console_level(level).file_level(level)
+    }
+
+    /// Log level of the console output, values above `TRACE` are ignored
+    pub fn console_level(mut self, level: u32) -> Self {
+        if level <= TRACE {
+            self.console_level = Some(level);
+        }
+        self
+    }
+
+    /// Log level of the file output, values above `TRACE` are ignored
+    pub fn file_level(mut self, level: u32) -> Self {
+        if level <= TRACE {
+            self.file_level = Some(level);
+        }
+        self
+    }
+
+    /// Per-target levels, checked before the level of each sink
+    ///
+    /// The console and the file write every record passing the filter,
+    /// unless their levels are set explicitly.
+    /// ```no_run
+    /// use se_logger::*;
+    ///
+    /// let logger = Logger::builder()
+    ///     .filter(Filter::parse("info,myapp::db=trace,hyper=warning").unwrap())
+    ///     .build();
+    /// ```
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Use the filter in the `SE_LOG_LEVEL` environment variable,
+    /// see [`Filter`] for the syntax
+    ///
+    /// Keeps the current filter if the variable is not set,
+    /// invalid values are reported and ignored.
+    pub fn env_filter(mut self) -> Self {
+        match Filter::from_env() {
+            Some(Ok(filter)) => self.filter = Some(filter),
+            Some(Err(e)) => eprintln!("Logger: Invalid {}: {e}", crate::filter::LEVEL_VAR),
+            None => {}
+        }
+        self
+    }
+
+    /// Enable or disable printing messages to stdout
+    pub fn console(mut self, console: bool) -> Self {
+        self.console = match console {
+            true => Console::Stdout,
+            false => Console::Off,
+        };
+        self
+    }
+
+    /// Where console output is written, see [`Console`]
+    ///
+    /// ```no_run
+    /// use se_logger::*;
+    ///
+    /// // Keep stdout clean for piped output
+    /// let logger = Logger::builder()
+    ///     .console_target(Console::WARNING_TO_STDERR)
+    ///     .build();
+    /// ```
+    pub fn console_target(mut self, console: Console) -> Self {
+        self.console = console;
+        self
+    }
+
+    /// Enable or disable writing to the log file
+    pub fn file(mut self, file: bool) -> Self {
+        self.file_enabled = file;
+        self
+    }
+
+    /// Layout used to render log lines on the console and in the file
+    pub fn format(mut self, format: Format) -> Self {
+        self.console_format = format.clone();
+        self.file_format = format;
+        self
+    }
+
+    /// Layout used to render log lines on the console
+    pub fn console_format(mut self, format: Format) -> Self {
+        self.console_format = format;
+        self
+    }
+
+    /// Layout used to render log lines in the file
+    pub fn file_format(mut self, format: Format) -> Self {
+        self.file_format = format;
+        self
+    }
+
+    /// Whether console output is colored, the log file is never colored
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Console colors of the levels
+    pub fn colors(mut self, colors: Colors) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// When buffered records are written to the log file,
+    /// flushes after every record by default
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.file = self.file.flush_policy(policy);
+        self
+    }
+
+    /// Records with this level or a more severe one are always
+    /// flushed immediately, `ERROR` by default
+    pub fn flush_level(mut self, level: u32) -> Self {
+        self.file = self.file.flush_level(level);
+        self
+    }
+
+    /// Write records on a background thread
+    ///
+    /// Logging pushes records onto a queue holding up to `capacity`
+    /// records, `policy` decides what happens when it is full.
+    /// Formatting and I/O happen on the writer thread.
+    pub fn asynchronous(mut self, capacity: usize, policy: OverflowPolicy) -> Self {
+        self.queue = Some((capacity, policy));
+        self
+    }
+
+    /// Rotate the log file when it would grow past `bytes`,
+    /// see [`FileSinkBuilder::max_file_size`]
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.file = self.file.max_file_size(bytes);
+        self
+    }
+
+    /// Number of rotated files kept next to the active one, 5 by default
+    pub fn max_files(mut self, count: usize) -> Self {
+        self.file = self.file.max_files(count);
+        self
+    }
+
+    /// When the path pattern is expanded again to switch to a new file,
+    /// see [`FileSinkBuilder::rotation`]
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.file = self.file.rotation(rotation);
+        self
+    }
+
+    /// Remove or archive old log files produced by the path pattern
+    ///
+    /// Applied when the logger is built and after every rotation.
+    pub fn retention(mut self, retention: Retention) -> Self {
+        self.file = self.file.retention(retention);
+        self
+    }
+
+    /// Compress rotated log files on a background thread
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.file = self.file.compression(compression);
+        self
+    }
+
+    /// Add a sink, writing records with `level` or a more severe one
+    /// rendered with `format`
+    ///
+    /// ```no_run
+    /// use se_logger::*;
+    ///
+    /// // Everything to the file, only errors to the console
+    /// let logger = Logger::builder()
+    ///     .console(false)
+    ///     .file_level(DEBUG)
+    ///     .sink(ConsoleSink::new(Console::Stderr), ERROR, Format::Bracket)
+    ///     .build();
+    /// ```
+    pub fn sink(self, sink: impl Sink + 'static, level: u32, format: Format) -> Self {
+        self.shared_sink(Arc::new(sink), level, format)
+    }
+
+    /// Add a sink that may also be used by other loggers, see
+    /// [`LoggerBuilder::sink`]
+    ///
+    /// A `ConfigWatcher` reload keeps sinks added this way open if the
+    /// new logger shares them.
+    pub fn shared_sink(mut self, sink: Arc<dyn Sink>, level: u32, format: Format) -> Self {
+        self.sinks
+            .push(SinkEntry::new(sink, level.min(TRACE), format, None));
+        self
+    }
+
+    /// Levels of the console and the file
+    pub(crate) fn levels(&self) -> (u32, u32) {
+        // With a filter, the console and the file only filter by level
+        // if asked to
+        let default_level = match self.filter {
+            Some(_) => TRACE,
+            None => INFO,
+        };
+        (
+            self.console_level.unwrap_or(default_level),
+            self.file_level.unwrap_or(default_level),
+        )
+    }
+
+    /// The filter set with [`LoggerBuilder::filter`]
+    #[cfg(feature = "config")]
+    pub(crate) fn filter_ref(&self) -> Option<&Filter> {
+        self.filter.as_ref()
+    }
+
+    /// Keep every record in `ring`, at `TRACE` regardless of the other
+    /// levels, to dump them with [`Logger::dump_ring`] when needed
+    ///
+    /// Records rejected by the filter are not kept.
+    /// ```no_run
+    /// use se_logger::*;
+    ///
+    /// Logger::builder()
+    ///     .level(INFO)
+    ///     .ring_buffer(RingSink::with_records(10_000))
+    ///     .build()
+    ///     .install();
+    /// // On failure, write the detailed history next to the log file
+    /// dump_ring_to("crash_history.log").unwrap();
+    /// ```
+    pub fn ring_buffer(mut self, ring: RingSink) -> Self {
+        self.ring = Some(ring);
+        self
+    }
+
+    /// Build the logger, expanding the path pattern with the current time
+    pub fn build(self) -> Logger {
+        let (console_level, file_level) = self.levels();
+        let mut sinks = Vec::new();
+        if self.console != Console::Off {
+            let console = ConsoleSink::new(self.console)
+                .color(self.color)
+                .colors(self.colors);
+            sinks.push(SinkEntry::new(
+                Arc::new(console),
+                console_level,
+                self.console_format,
+                Some(Builtin::Console),
+            ));
+        }
+        let file = self.file_enabled.then(|| self.file.build());
+        if let Some(file) = &file {
+            sinks.push(SinkEntry::new(
+                Arc::new(file.clone()),
+                file_level,
+                self.file_format.clone(),
+                Some(Builtin::File),
+            ));
+        }
+        if let Some(ring) = &self.ring {
+            sinks.push(SinkEntry::new(
+                Arc::new(ring.clone()),
+                TRACE,
+                self.file_format.clone(),
+                None,
+            ));
+        }
+        sinks.extend(self.sinks);
+
+        let output = Arc::new(Output {
+            sinks,
+            raw_format: Format::Pattern(Pattern::parse("{m}").expect("valid pattern")),
+            kept: Mutex::new(Vec::new()),
+        });
+        let level = output.level(self.filter.as_ref());
+        let writer = self
+            .queue
+            .map(|(capacity, policy)| AsyncWriter::new(output.clone(), capacity, policy));
+        Logger {
+            level: AtomicU32::new(level),
+            filter: RwLock::new(self.filter),
+            output,
+            writer,
+            file,
+            ring: self.ring.map(|ring| (ring, self.file_format)),
+        }
+    }
+}
+
+impl Default for LoggerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+
+use crate::{global, Record};
+
+/// Log a trace message with `format!` syntax
+///
+/// The message is only formatted if `TRACE` is enabled.
+/// ```no_run
+/// # use se_logger::*;
+/// let id = 7;
+/// trace!("Polling connection {id}");
+/// ```
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        $crate::__log!($crate::TRACE, $($arg)+)
+    };
+}
+
+/// Log a debug message with `format!` syntax
+///
+/// The message is only formatted if `DEBUG` is enabled.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::__log!($crate::DEBUG, $($arg)+)
+    };
+}
+
+/// Log an info message with `format!` syntax
+///
+/// The message is only formatted if `INFO` is enabled.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::__log!($crate::INFO, $($arg)+)
+    };
+}
+
+/// Log a warning message with `format!` syntax
+///
+/// The message is only formatted if `WARNING` is enabled.
+#[macro_export]
+macro_rules! warning {
+    ($($arg:tt)+) => {
+        $crate::__log!($crate::WARNING, $($arg)+)
+    };
+}
+
+/// Log an error message with `format!` syntax
+///
+/// The message is only formatted if `ERROR` is enabled.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::__log!($crate::ERROR, $($arg)+)
+    };
+}
+
+/// Log a fatal message with `format!` syntax
+#[macro_export]
+macro_rules! fatal {
+    ($($arg:tt)+) => {
+        $crate::__log!($crate::FATAL, $($arg)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    ($level:expr, $($arg:tt)+) => {{
+        let level = $level;
+        if $crate::__enabled(level, module_path!()) {
+            $crate::__log_args(
+                level,
+                format_args!($($arg)+),
+                module_path!(),
+                file!(),
+                line!(),
+            );
+        }
+    }};
+}
+
+#[doc(hidden)]
+pub fn __enabled(level: u32, target: &str) -> bool {
+    global().enabled_for(target, level)
+}
+
+#[doc(hidden)]
+pub fn __log_args(
+    level: u32,
+    args: fmt::Arguments,
+    module_path: &'static str,
+    file: &'static str,
+    line: u32,
+) {
+    let mut record = Record::new(level, &args.to_string());
+    record.target = module_path.to_string();
+    record.module_path = Some(module_path.to_string());
+    record.file = Some(file.to_string());
+    record.line = Some(line);
+    global().log_record(&record);
+}
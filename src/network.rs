@@ -0,0 +1,810 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{Format, Record, Sink};
+
+/// Transport protocol of a [`NetworkSink`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetworkProtocol {
+    /// A TCP stream, reconnected after errors
+    #[default]
+    Tcp,
+    /// One datagram per record
+    Udp,
+}
+
+/// How records are delimited on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// A line feed after every record
+    #[default]
+    Newline,
+    /// The length of the record as a 4-byte big-endian integer before it
+    LengthPrefixed,
+}
+
+/// Sink streaming records to a collector over TCP or UDP
+///
+/// Records are rendered with `format`, framed and queued, a background
+/// thread sends them. While the collector is unreachable, records stay
+/// queued and the thread reconnects with exponential backoff. A full
+/// queue discards the oldest record, unless a spill file is set: records
+/// are then appended to it and sent once the queue has drained. Records
+/// still queued when the sink is closed are moved to the spill file and
+/// sent by the next sink using it, records logged afterwards are
+/// discarded.
+/// ```
+/// use se_logger::*;
+/// use std::io::{BufRead, BufReader};
+/// use std::net::TcpListener;
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let sink = NetworkSink::builder(&listener.local_addr().unwrap().to_string())
+///     .framing(Framing::Newline)
+///     .build();
+/// sink.write(&Record::new(INFO, "hello"), &Format::Logfmt).unwrap();
+///
+/// let (stream, _) = listener.accept().unwrap();
+/// let mut line = String::new();
+/// BufReader::new(stream).read_line(&mut line).unwrap();
+/// assert!(line.contains("msg=hello"));
+/// ```
+#[derive(Debug)]
+pub struct NetworkSink {
+    shared: Arc<Shared>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Builder for [`NetworkSink`]
+#[derive(Clone)]
+pub struct NetworkSinkBuilder {
+    addr: String,
+    protocol: NetworkProtocol,
+    framing: Framing,
+    capacity: usize,
+    spill: Option<PathBuf>,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    timeout: Duration,
+    #[cfg(feature = "tls")]
+    tls: Option<(String, Arc<rustls::ClientConfig>)>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: NetworkSinkBuilder,
+    state: Mutex<State>,
+    /// Signals queued records and closing to the sender thread
+    work: Condvar,
+    /// Signals progress of the sender thread to `flush`
+    progress: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queue: VecDeque<Entry>,
+    next_id: u64,
+    spill: Option<Spill>,
+    dropped: usize,
+    /// The sender thread waits before reconnecting
+    backoff: bool,
+    closed: bool,
+}
+
+/// A framed record
+#[derive(Debug)]
+struct Entry {
+    id: u64,
+    data: Vec<u8>,
+}
+
+/// Records written to disk while the queue is full,
+/// each prefixed with its length as a 4-byte little-endian integer
+#[derive(Debug)]
+struct Spill {
+    file: File,
+    /// Offset of the next record to send
+    read: u64,
+    len: u64,
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+    #[cfg(feature = "tls")]
+    Tls(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+}
+
+impl NetworkSink {
+    /// Create a builder for a sink sending to `addr` (`host:port`)
+    pub fn builder(addr: &str) -> NetworkSinkBuilder {
+        NetworkSinkBuilder::new(addr)
+    }
+
+    fn push(&self, data: Vec<u8>) {
+        let config = &self.shared.config;
+        let mut state = self.shared.lock();
+        let spilling = state.spill.as_ref().is_some_and(Spill::pending);
+        if state.closed || spilling || state.queue.len() >= config.capacity {
+            if let Some(spill) = state.spill.as_mut() {
+                match spill.append(&data) {
+                    Ok(()) => {
+                        drop(state);
+                        self.shared.work.notify_one();
+                        return;
+                    }
+                    Err(e) => eprintln!("Logger: Failed to write spill file: {e}"),
+                }
+            }
+            if state.closed {
+                return;
+            }
+            while state.queue.len() >= config.capacity {
+                state.queue.pop_front();
+                state.dropped += 1;
+            }
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.queue.push_back(Entry { id, data });
+        drop(state);
+        self.shared.work.notify_one();
+    }
+}
+
+impl Sink for NetworkSink {
+    fn write(&self, record: &Record, format: &Format) -> io::Result<()> {
+        let line = format.format(record);
+        // Framed records and spill file records have a 32-bit length
+        if u32::try_from(line.len() + 4).is_err() {
+            return Err(too_large());
+        }
+        let data = match self.shared.config.framing {
+            Framing::Newline => [line.as_bytes(), b"\n"].concat(),
+            Framing::LengthPrefixed => {
+                let len = line.len() as u32;
+                [&len.to_be_bytes()[..], line.as_bytes()].concat()
+            }
+        };
+        self.push(data);
+        Ok(())
+    }
+
+    /// Wait until the queued records are sent, returns early while
+    /// the collector is unreachable
+    fn flush(&self) -> io::Result<()> {
+        let mut state = self.shared.lock();
+        while state.pending() && !state.backoff && !state.closed {
+            state = self
+                .shared
+                .progress
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        Ok(())
+    }
+
+    /// Send the queued records if connected, move the rest to the spill
+    /// file, stop the sender thread and release the spill file
+    fn close(&self) -> io::Result<()> {
+        self.shared.lock().closed = true;
+        self.shared.work.notify_all();
+        let thread = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for NetworkSink {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+impl NetworkSinkBuilder {
+    fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.to_string(),
+            protocol: NetworkProtocol::default(),
+            framing: Framing::default(),
+            capacity: 10_000,
+            spill: None,
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            timeout: Duration::from_secs(5),
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+
+    /// Transport protocol, TCP by default
+    pub fn protocol(mut self, protocol: NetworkProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// How records are delimited, a line feed after every record by default
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Number of records kept in memory while disconnected, 10000 by default
+    pub fn capacity(mut self, records: usize) -> Self {
+        self.capacity = records.max(1);
+        self
+    }
+
+    /// Append records to this file when the queue is full, instead of
+    /// discarding the oldest ones
+    ///
+    /// Records left in the file by a previous run are sent first. The file
+    /// is locked while the sink exists, a second sink using it reports
+    /// an error and runs without a spill file.
+    pub fn spill_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.spill = Some(path.into());
+        self
+    }
+
+    /// Wait between reconnection attempts, doubling from `min` up to
+    /// `max` after every failure. 100 ms and 30 s by default.
+    pub fn backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    /// Timeout for connecting and sending, 5 s by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Encrypt the TCP stream, verifying the certificate of
+    /// `server_name` against the Mozilla root certificates
+    #[cfg(feature = "tls")]
+    pub fn tls(self, server_name: &str) -> Self {
+        let roots = rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .expect("ring supports the default protocol versions")
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        self.tls_config(server_name, Arc::new(config))
+    }
+
+    /// Encrypt the TCP stream with a custom rustls configuration,
+    /// e.g. for a private certificate authority
+    #[cfg(feature = "tls")]
+    pub fn tls_config(mut self, server_name: &str, config: Arc<rustls::ClientConfig>) -> Self {
+        self.tls = Some((server_name.to_string(), config));
+        self
+    }
+
+    /// Build the sink and start its sender thread
+    pub fn build(self) -> NetworkSink {
+        let spill = self
+            .spill
+            .as_ref()
+            .and_then(|path| match Spill::open(path) {
+                Ok(spill) => Some(spill),
+                Err(e) => {
+                    eprintln!("Logger: Failed to open spill file: {e}");
+                    None
+                }
+            });
+        let shared = Arc::new(Shared {
+            config: self,
+            state: Mutex::new(State {
+                spill,
+                ..State::default()
+            }),
+            work: Condvar::new(),
+            progress: Condvar::new(),
+        });
+        let thread = {
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name("se-logger network".to_string())
+                .spawn(move || run(&shared))
+        };
+        let thread = match thread {
+            Ok(thread) => Some(thread),
+            Err(e) => {
+                eprintln!("Logger: Failed to start network thread: {e}");
+                shared.lock().closed = true;
+                None
+            }
+        };
+        NetworkSink {
+            shared,
+            thread: Mutex::new(thread),
+        }
+    }
+}
+
+impl std::fmt::Debug for NetworkSinkBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("NetworkSinkBuilder");
+        s.field("addr", &self.addr)
+            .field("protocol", &self.protocol)
+            .field("framing", &self.framing)
+            .field("capacity", &self.capacity)
+            .field("spill", &self.spill)
+            .field("min_backoff", &self.min_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("timeout", &self.timeout);
+        #[cfg(feature = "tls")]
+        s.field("tls", &self.tls.as_ref().map(|(name, _)| name));
+        s.finish()
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn connect(&self) -> io::Result<Connection> {
+        let config = &self.config;
+        let addr = config
+            .addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address not found"))?;
+        match config.protocol {
+            NetworkProtocol::Udp => {
+                let socket = UdpSocket::bind(if addr.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                })?;
+                socket.connect(addr)?;
+                Ok(Connection::Udp(socket))
+            }
+            NetworkProtocol::Tcp => {
+                let stream = TcpStream::connect_timeout(&addr, config.timeout)?;
+                stream.set_write_timeout(Some(config.timeout))?;
+                stream.set_nodelay(true)?;
+                #[cfg(feature = "tls")]
+                if let Some((name, tls)) = &config.tls {
+                    let name = rustls::pki_types::ServerName::try_from(name.clone())
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                    let connection = rustls::ClientConnection::new(tls.clone(), name)
+                        .map_err(io::Error::other)?;
+                    return Ok(Connection::Tls(Box::new(rustls::StreamOwned::new(
+                        connection, stream,
+                    ))));
+                }
+                Ok(Connection::Tcp(stream))
+            }
+        }
+    }
+}
+
+impl State {
+    fn pending(&self) -> bool {
+        !self.queue.is_empty() || self.spill.as_ref().is_some_and(Spill::pending)
+    }
+}
+
+impl Connection {
+    fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.write_all(data),
+            Connection::Udp(socket) => socket.send(data).map(drop),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.write_all(data).and_then(|_| stream.flush()),
+        }
+    }
+}
+
+impl Spill {
+    fn open(path: &PathBuf) -> io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        // Each sink keeps its own offsets into the file
+        file.try_lock().map_err(|e| match e {
+            std::fs::TryLockError::WouldBlock => io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                "the file is used by another sink",
+            ),
+            std::fs::TryLockError::Error(e) => e,
+        })?;
+        let len = file.metadata()?.len();
+        Ok(Self { file, read: 0, len })
+    }
+
+    fn pending(&self) -> bool {
+        self.read < self.len
+    }
+
+    fn append(&mut self, data: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(data.len()).map_err(|_| too_large())?;
+        self.file.seek(SeekFrom::Start(self.len))?;
+        self.file.write_all(&len.to_le_bytes())?;
+        self.file.write_all(data)?;
+        self.len += 4 + len as u64;
+        Ok(())
+    }
+
+    /// Read the next record without consuming it
+    fn peek(&mut self) -> io::Result<Vec<u8>> {
+        self.file.seek(SeekFrom::Start(self.read))?;
+        let mut len = [0; 4];
+        self.file.read_exact(&mut len)?;
+        let mut data = vec![0; u32::from_le_bytes(len) as usize];
+        self.file.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    /// Consume the record returned by `peek`, empties the file
+    /// once everything was sent
+    fn advance(&mut self, len: usize) -> io::Result<()> {
+        self.read += 4 + len as u64;
+        if self.read >= self.len {
+            self.file.set_len(0)?;
+            self.read = 0;
+            self.len = 0;
+        }
+        Ok(())
+    }
+
+    /// Discard unreadable contents
+    fn reset(&mut self) {
+        let _ = self.file.set_len(0);
+        self.read = 0;
+        self.len = 0;
+    }
+}
+
+/// Where the record being sent came from
+enum Source {
+    Queue(u64),
+    Spill(usize),
+}
+
+fn run(shared: &Shared) {
+    let config = &shared.config;
+    let mut connection: Option<Connection> = None;
+    let mut backoff = config.min_backoff;
+    loop {
+        // Take the oldest record, it stays queued until it was sent
+        let (data, source) = {
+            let mut state = shared.lock();
+            while !state.pending() && !state.closed {
+                state = shared
+                    .work
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            if !state.pending() || (state.closed && connection.is_none() && state.backoff) {
+                break;
+            }
+            match state.queue.front() {
+                Some(entry) => (entry.data.clone(), Source::Queue(entry.id)),
+                None => {
+                    let Some(spill) = state.spill.as_mut() else {
+                        continue;
+                    };
+                    match spill.peek() {
+                        Ok(data) => {
+                            let len = data.len();
+                            (data, Source::Spill(len))
+                        }
+                        Err(e) => {
+                            eprintln!("Logger: Failed to read spill file: {e}");
+                            spill.reset();
+                            continue;
+                        }
+                    }
+                }
+            }
+        };
+
+        if connection.is_none() {
+            match shared.connect() {
+                Ok(c) => {
+                    connection = Some(c);
+                    backoff = config.min_backoff;
+                    let mut state = shared.lock();
+                    state.backoff = false;
+                    let dropped = std::mem::take(&mut state.dropped);
+                    if dropped > 0 {
+                        eprintln!("Logger: Network sink dropped {dropped} messages");
+                    }
+                }
+                Err(e) => {
+                    wait_backoff(shared, &e, &mut backoff);
+                    continue;
+                }
+            }
+        }
+        let result = match connection.as_mut() {
+            Some(c) => c.send(&data),
+            None => continue,
+        };
+        match result {
+            Ok(()) => {}
+            // Retrying would fail again, the record is discarded
+            Err(e) if rejects_record(&e) => {
+                eprintln!("Logger: Discarded a record of {} bytes: {e}", data.len());
+            }
+            Err(e) => {
+                connection = None;
+                wait_backoff(shared, &e, &mut backoff);
+                continue;
+            }
+        }
+
+        let mut state = shared.lock();
+        match source {
+            Source::Queue(id) => {
+                // The record may have been discarded meanwhile
+                if state.queue.front().is_some_and(|entry| entry.id == id) {
+                    state.queue.pop_front();
+                }
+            }
+            Source::Spill(len) => {
+                if let Some(spill) = state.spill.as_mut() {
+                    if let Err(e) = spill.advance(len) {
+                        eprintln!("Logger: Failed to truncate spill file: {e}");
+                    }
+                }
+            }
+        }
+        drop(state);
+        shared.progress.notify_all();
+    }
+    spill_remaining(shared);
+    // Unlock the file for the next sink
+    shared.lock().spill = None;
+    shared.progress.notify_all();
+}
+
+/// Returns `true` if `error` was caused by the record rather than by the
+/// connection, e.g. a datagram over the size limit
+fn rejects_record(error: &io::Error) -> bool {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const EMSGSIZE: i32 = 90;
+    #[cfg(windows)]
+    const EMSGSIZE: i32 = 10040;
+    #[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+    const EMSGSIZE: i32 = 40;
+    error.raw_os_error() == Some(EMSGSIZE)
+}
+
+/// Error for records whose length does not fit into 32 bits
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "the record is 4 GiB or larger")
+}
+
+/// Report a connection error and wait before the next attempt,
+/// returns early when the sink is closed
+fn wait_backoff(shared: &Shared, error: &io::Error, backoff: &mut Duration) {
+    let mut state = shared.lock();
+    if !state.backoff {
+        eprintln!("Logger: Failed to send to {}: {error}", shared.config.addr);
+    }
+    state.backoff = true;
+    shared.progress.notify_all();
+    let until = Instant::now() + *backoff;
+    while !state.closed {
+        let now = Instant::now();
+        if now >= until {
+            break;
+        }
+        state = shared
+            .work
+            .wait_timeout(state, until - now)
+            .unwrap_or_else(PoisonError::into_inner)
+            .0;
+    }
+    *backoff = (*backoff * 2).min(shared.config.max_backoff);
+}
+
+/// Move the records left in the queue to the spill file
+fn spill_remaining(shared: &Shared) {
+    let mut state = shared.lock();
+    let state = &mut *state;
+    let Some(spill) = state.spill.as_mut() else {
+        return;
+    };
+    // Unsent spilled records are newer than the queued ones,
+    // so the file is rewritten with the queue first
+    let mut rest = Vec::new();
+    if spill.pending() {
+        let _ = spill.file.seek(SeekFrom::Start(spill.read));
+        if let Err(e) = spill.file.read_to_end(&mut rest) {
+            eprintln!("Logger: Failed to read spill file: {e}");
+        }
+    }
+    spill.reset();
+    for entry in state.queue.drain(..) {
+        if let Err(e) = spill.append(&entry.data) {
+            eprintln!("Logger: Failed to write spill file: {e}");
+            return;
+        }
+    }
+    let _ = spill.file.seek(SeekFrom::Start(spill.len));
+    match spill.file.write_all(&rest) {
+        Ok(()) => spill.len += rest.len() as u64,
+        Err(e) => eprintln!("Logger: Failed to write spill file: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::INFO;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn format() -> Format {
+        Format::Pattern(crate::Pattern::parse("{m}").unwrap())
+    }
+
+    fn log(sink: &NetworkSink, message: &str) {
+        sink.write(&Record::new(INFO, message), &format()).unwrap();
+    }
+
+    /// Address of a TCP port that nothing listens on
+    fn closed_port() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    fn read_lines(listener: &TcpListener, count: usize) -> Vec<String> {
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        BufReader::new(stream)
+            .lines()
+            .take(count)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn length_prefixed_framing() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let sink = NetworkSink::builder(&listener.local_addr().unwrap().to_string())
+            .framing(Framing::LengthPrefixed)
+            .build();
+        log(&sink, "first");
+        log(&sink, "multi\nline");
+
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        for expected in ["first", "multi\nline"] {
+            let mut len = [0; 4];
+            stream.read_exact(&mut len).unwrap();
+            let mut data = vec![0; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut data).unwrap();
+            assert_eq!(data, expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn udp_datagrams() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let sink = NetworkSink::builder(&socket.local_addr().unwrap().to_string())
+            .protocol(NetworkProtocol::Udp)
+            .build();
+        log(&sink, "first");
+        log(&sink, "second");
+
+        let mut buf = [0; 1024];
+        for expected in ["first\n", "second\n"] {
+            let len = socket.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn udp_discards_oversized_records() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let sink = NetworkSink::builder(&socket.local_addr().unwrap().to_string())
+            .protocol(NetworkProtocol::Udp)
+            .build();
+        log(&sink, &"x".repeat(70_000));
+        log(&sink, "small");
+
+        let mut buf = [0; 1024];
+        let len = socket.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"small\n");
+    }
+
+    #[test]
+    fn reconnects_with_backoff() {
+        let addr = closed_port();
+        let sink = NetworkSink::builder(&addr)
+            .backoff(Duration::from_millis(10), Duration::from_millis(50))
+            .build();
+        for i in 0..3 {
+            log(&sink, &format!("record {i}"));
+        }
+        // Returns while the collector is unreachable
+        sink.flush().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+
+        let listener = TcpListener::bind(&addr).unwrap();
+        log(&sink, "record 3");
+        assert_eq!(
+            read_lines(&listener, 4),
+            ["record 0", "record 1", "record 2", "record 3"]
+        );
+    }
+
+    #[test]
+    fn spill_file_is_exclusive() {
+        let spill = std::env::temp_dir().join(format!(
+            "se-logger-network-{}-exclusive.spill",
+            std::process::id()
+        ));
+        let addr = closed_port();
+        let first = NetworkSink::builder(&addr).spill_file(&spill).build();
+        let second = NetworkSink::builder(&addr).spill_file(&spill).build();
+        assert!(first.shared.lock().spill.is_some());
+        assert!(second.shared.lock().spill.is_none());
+
+        // Closing releases the file
+        first.close().unwrap();
+        let third = NetworkSink::builder(&addr).spill_file(&spill).build();
+        assert!(third.shared.lock().spill.is_some());
+        drop((first, second, third));
+        std::fs::remove_file(spill).unwrap();
+    }
+
+    #[test]
+    fn full_queue_spills_to_file() {
+        let spill =
+            std::env::temp_dir().join(format!("se-logger-network-{}.spill", std::process::id()));
+        let _ = std::fs::remove_file(&spill);
+        let addr = closed_port();
+
+        // Records logged while the collector is down outlive the sink
+        let sink = NetworkSink::builder(&addr)
+            .capacity(2)
+            .spill_file(&spill)
+            .backoff(Duration::from_millis(10), Duration::from_millis(50))
+            .build();
+        for i in 0..10 {
+            log(&sink, &format!("record {i}"));
+        }
+        sink.close().unwrap();
+        assert!(std::fs::metadata(&spill).unwrap().len() > 0);
+
+        let listener = TcpListener::bind(&addr).unwrap();
+        let sink = NetworkSink::builder(&addr).spill_file(&spill).build();
+        log(&sink, "record 10");
+        let expected: Vec<String> = (0..=10).map(|i| format!("record {i}")).collect();
+        assert_eq!(read_lines(&listener, 11), expected);
+        sink.close().unwrap();
+        assert_eq!(std::fs::metadata(&spill).unwrap().len(), 0);
+        std::fs::remove_file(spill).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::fmt::Write;
+use std::panic::PanicHookInfo;
+use std::sync::Once;
+
+use crate::{global, Record, FATAL};
+
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    /// Set while the thread writes to sinks or runs the hook, a panic
+    /// there is only printed, the sink may be locked or panic again
+    static IN_SINKS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks the current thread as writing to sinks until dropped
+pub(crate) struct SinkGuard(bool);
+
+impl SinkGuard {
+    pub(crate) fn enter() -> Self {
+        Self(IN_SINKS.replace(true))
+    }
+}
+
+impl Drop for SinkGuard {
+    fn drop(&mut self) {
+        IN_SINKS.set(self.0);
+    }
+}
+
+/// Log panics with the process-global logger
+///
+/// Installs a panic hook writing a `FATAL` record with the panic message,
+/// its location, the name of the panicking thread and a backtrace, then
+/// flushing all sinks. The previously installed hook runs afterwards, so
+/// the default message on stderr is kept. Panics of sinks and of the
+/// writer thread are only printed to stderr. Installing the hook more
+/// than once has no effect.
+/// ```no_run
+/// use se_logger::*;
+///
+/// log_init("app_%F.log", INFO);
+/// install_panic_hook();
+///
+/// std::thread::Builder::new()
+///     .name("worker".to_string())
+///     .spawn(|| panic!("queue corrupted"))
+///     .unwrap();
+/// ```
+/// Output:
+/// ```text
+/// [19:35:33] [FATAL] [worker] thread 'worker' panicked at src/main.rs:9:15:
+/// queue corrupted
+/// stack backtrace:
+/// ...
+/// ```
+pub fn install_panic_hook() {
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            log_panic(info);
+            previous(info);
+        }));
+    });
+}
+
+fn log_panic(info: &PanicHookInfo<'_>) {
+    let payload = info.payload();
+    let message = match payload.downcast_ref::<&str>() {
+        Some(s) => s,
+        None => match payload.downcast_ref::<String>() {
+            Some(s) => s.as_str(),
+            None => "Box<dyn Any>",
+        },
+    };
+
+    let mut record = Record::new(FATAL, "");
+    let mut text = format!("thread '{}' panicked", record.thread);
+    if let Some(location) = info.location() {
+        let _ = write!(text, " at {location}");
+        record.file = Some(location.file().to_string());
+        record.line = Some(location.line());
+    }
+    let _ = write!(
+        text,
+        ":\n{message}\nstack backtrace:\n{}",
+        Backtrace::force_capture()
+    );
+    if IN_SINKS.get() {
+        eprintln!("Logger: Panicked while writing a record: {text}");
+        return;
+    }
+    record.message = text;
+    record.target = "panic".to_string();
+
+    let _guard = SinkGuard::enter();
+    let logger = global();
+    logger.log_record(&record);
+    logger.flush();
+}
@@ -0,0 +1,302 @@
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use chrono::format::{Item, StrftimeItems};
+
+use crate::{level_to_string, Record};
+
+const DEFAULT_DATE: &str = "%F %T";
+
+/// User-defined layout of a log line, used with [`Format::Pattern`](crate::Format::Pattern)
+///
+/// Patterns are parsed once and validated when created. Text outside of
+/// braces is copied as is, `{{` and `}}` produce literal braces.
+///
+/// | Token | Value |
+/// |-------|-------|
+/// | `{d}`, `{d(%F %T%.3f)}` | Timestamp, formatted with [strftime](https://docs.rs/chrono/latest/chrono/format/strftime/index.html), `%F %T` by default |
+/// | `{l}` | Level |
+/// | `{T}` | Thread name |
+/// | `{I}` | Thread id |
+/// | `{t}` | Target |
+/// | `{M}` | Module path |
+/// | `{f}` | Source file |
+/// | `{L}` | Source line |
+/// | `{P}` | Process id |
+/// | `{m}` | Message |
+/// | `{K}` | Fields, as `key=value` pairs |
+///
+/// Tokens accept modifiers after a colon: an alignment (`<`, `>` or `^`,
+/// left by default) with a minimum width, and a maximum width after a dot.
+/// `{l:<7}` pads the level to 7 characters, `{M:>20.20}` right-aligns the
+/// module path and truncates it to 20 characters.
+/// ```
+/// use se_logger::*;
+///
+/// let pattern = Pattern::parse("{d(%F %T%.3f)} {l:<7} [{T}] {M}:{L} - {m}").unwrap();
+/// let logger = Logger::builder().format(Format::Pattern(pattern));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pieces: Vec<Piece>,
+}
+
+/// Error returned when parsing an invalid [`Pattern`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    /// Byte offset of the error in the pattern
+    pub position: usize,
+    /// Description of the error
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Token(Token, Modifiers),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Date(String),
+    Level,
+    ThreadName,
+    ThreadId,
+    Target,
+    Module,
+    File,
+    Line,
+    Pid,
+    Message,
+    Fields,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Modifiers {
+    align: Align,
+    min: usize,
+    max: Option<usize>,
+}
+
+impl Pattern {
+    /// Parse a pattern, see [`Pattern`] for the syntax
+    pub fn parse(pattern: &str) -> Result<Self, PatternError> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = pattern.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|&(_, c)| c == '{').is_some() => text.push('{'),
+                '}' if chars.next_if(|&(_, c)| c == '}').is_some() => text.push('}'),
+                '{' => {
+                    let end = match pattern[i..].find('}') {
+                        Some(end) => i + end,
+                        None => return Err(PatternError::new(i, "unclosed `{`")),
+                    };
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(parse_token(&pattern[i + 1..end], i + 1)?);
+                    while chars.next_if(|&(j, _)| j <= end).is_some() {}
+                }
+                '}' => {
+                    return Err(PatternError::new(
+                        i,
+                        "unmatched `}`, use `}}` for a literal brace",
+                    ))
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Self { pieces })
+    }
+
+    /// Render a record
+    pub fn format(&self, record: &Record) -> String {
+        let mut line = String::with_capacity(64 + record.message.len());
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => line.push_str(text),
+                Piece::Token(token, modifiers) => {
+                    modifiers.apply(&mut line, &token.value(record));
+                }
+            }
+        }
+        line
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl PatternError {
+    fn new(position: usize, message: &str) -> Self {
+        Self {
+            position,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern at {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Parse the contents of `{...}`, `offset` is the position of `s` in the pattern
+fn parse_token(s: &str, offset: usize) -> Result<Piece, PatternError> {
+    // The strftime format can contain `:`, so the argument is split off first
+    let (name, arg, rest) = match s.find('(') {
+        Some(open) => match s[open..].find(')') {
+            Some(close) => (
+                &s[..open],
+                Some(&s[open + 1..open + close]),
+                &s[open + close + 1..],
+            ),
+            None => return Err(PatternError::new(offset + open, "unclosed `(`")),
+        },
+        None => match s.find(':') {
+            Some(colon) => (&s[..colon], None, &s[colon..]),
+            None => (s, None, ""),
+        },
+    };
+    let token = match (name, arg) {
+        ("d", arg) => {
+            let fmt = arg.unwrap_or(DEFAULT_DATE);
+            if StrftimeItems::new(fmt).any(|item| matches!(item, Item::Error)) {
+                return Err(PatternError::new(
+                    offset + name.len() + 1,
+                    &format!("invalid date format `{fmt}`"),
+                ));
+            }
+            Token::Date(fmt.to_string())
+        }
+        (_, Some(_)) => {
+            return Err(PatternError::new(
+                offset,
+                &format!("token `{name}` does not take an argument"),
+            ))
+        }
+        ("l", None) => Token::Level,
+        ("T", None) => Token::ThreadName,
+        ("I", None) => Token::ThreadId,
+        ("t", None) => Token::Target,
+        ("M", None) => Token::Module,
+        ("f", None) => Token::File,
+        ("L", None) => Token::Line,
+        ("P", None) => Token::Pid,
+        ("m", None) => Token::Message,
+        ("K", None) => Token::Fields,
+        ("", None) => return Err(PatternError::new(offset, "empty token")),
+        (name, None) => {
+            return Err(PatternError::new(
+                offset,
+                &format!("unknown token `{name}`"),
+            ))
+        }
+    };
+    let modifiers_offset = offset + s.len() - rest.len();
+    let modifiers = match rest {
+        "" => Modifiers::default(),
+        rest => match rest.strip_prefix(':') {
+            Some(m) => parse_modifiers(m, modifiers_offset + 1)?,
+            None => {
+                return Err(PatternError::new(
+                    modifiers_offset,
+                    "expected `:` before modifiers",
+                ))
+            }
+        },
+    };
+    Ok(Piece::Token(token, modifiers))
+}
+
+/// Parse `[<>^][min][.max]`
+fn parse_modifiers(s: &str, offset: usize) -> Result<Modifiers, PatternError> {
+    let (align, rest) = match s.chars().next() {
+        Some('<') => (Align::Left, &s[1..]),
+        Some('>') => (Align::Right, &s[1..]),
+        Some('^') => (Align::Center, &s[1..]),
+        _ => (Align::Left, s),
+    };
+    let (min, max) = match rest.split_once('.') {
+        Some((min, max)) => (min, Some(max)),
+        None => (rest, None),
+    };
+    let number = |n: &str| {
+        n.parse::<usize>()
+            .map_err(|_| PatternError::new(offset, &format!("invalid width in modifiers `{s}`")))
+    };
+    Ok(Modifiers {
+        align,
+        min: if min.is_empty() { 0 } else { number(min)? },
+        max: max.map(number).transpose()?,
+    })
+}
+
+impl Token {
+    fn value(&self, record: &Record) -> String {
+        match self {
+            Token::Date(fmt) => record.time.format(fmt).to_string(),
+            Token::Level => level_to_string(record.level),
+            Token::ThreadName => record.thread.clone(),
+            Token::ThreadId => record.thread_id.to_string(),
+            Token::Target => record.target.clone(),
+            Token::Module => record.module_path.clone().unwrap_or_default(),
+            Token::File => record.file.clone().unwrap_or_default(),
+            Token::Line => record.line.map(|l| l.to_string()).unwrap_or_default(),
+            Token::Pid => std::process::id().to_string(),
+            Token::Message => record.message.clone(),
+            Token::Fields => {
+                let mut fields = String::new();
+                for (i, (key, value)) in record.fields.iter().enumerate() {
+                    if i > 0 {
+                        fields.push(' ');
+                    }
+                    let _ = write!(fields, "{key}={value}");
+                }
+                fields
+            }
+        }
+    }
+}
+
+impl Modifiers {
+    fn apply(&self, out: &mut String, value: &str) {
+        let value = match self.max {
+            Some(max) => match value.char_indices().nth(max) {
+                Some((i, _)) => &value[..i],
+                None => value,
+            },
+            None => value,
+        };
+        let pad = self.min.saturating_sub(value.chars().count());
+        let (left, right) = match self.align {
+            Align::Left => (0, pad),
+            Align::Right => (pad, 0),
+            Align::Center => (pad / 2, pad - pad / 2),
+        };
+        out.push_str(&" ".repeat(left));
+        out.push_str(value);
+        out.push_str(&" ".repeat(right));
+    }
+}
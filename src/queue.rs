@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+
+use crate::logger::Output;
+use crate::panic::SinkGuard;
+use crate::{Record, WARNING};
+
+/// What to do when the queue of an asynchronous logger is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Wait until the writer thread makes room
+    #[default]
+    Block,
+    /// Discard the record being logged
+    DropNewest,
+    /// Discard the oldest queued record
+    DropOldest,
+}
+
+/// Hands records to a writer thread through a bounded queue
+#[derive(Debug)]
+pub(crate) struct AsyncWriter {
+    shared: Arc<Shared>,
+    output: Arc<Output>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    capacity: usize,
+    policy: OverflowPolicy,
+    queue: Mutex<Queue>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    entries: VecDeque<Entry>,
+    dropped: usize,
+    closed: bool,
+}
+
+#[derive(Debug)]
+enum Entry {
+    Record(Record),
+    Raw(String),
+    Flush(Sender<()>),
+}
+
+impl Entry {
+    fn is_message(&self) -> bool {
+        !matches!(self, Entry::Flush(_))
+    }
+}
+
+impl AsyncWriter {
+    pub(crate) fn new(output: Arc<Output>, capacity: usize, policy: OverflowPolicy) -> Self {
+        let shared = Arc::new(Shared {
+            capacity: capacity.max(1),
+            policy,
+            queue: Mutex::new(Queue::default()),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        });
+        let thread = {
+            let shared = shared.clone();
+            let output = output.clone();
+            std::thread::Builder::new()
+                .name("se-logger writer".to_string())
+                .spawn(move || run(&shared, &output))
+        };
+        let thread = match thread {
+            Ok(thread) => Some(thread),
+            Err(e) => {
+                eprintln!("Logger: Failed to start writer thread: {e}");
+                shared.lock().closed = true;
+                None
+            }
+        };
+        Self {
+            shared,
+            output,
+            thread: Mutex::new(thread),
+        }
+    }
+
+    pub(crate) fn write_record(&self, record: Record) {
+        if let Some(Entry::Record(record)) = self.push(Entry::Record(record)) {
+            self.output.write_record(&record);
+        }
+    }
+
+    pub(crate) fn write_raw(&self, message: &str) {
+        if let Some(Entry::Raw(message)) = self.push(Entry::Raw(message.to_string())) {
+            self.output.write_raw(&message);
+        }
+    }
+
+    /// Wait until all queued records are written and flushed
+    pub(crate) fn flush(&self) {
+        let (tx, rx) = mpsc::channel();
+        if self.push(Entry::Flush(tx)).is_some() {
+            self.output.flush();
+        } else {
+            let _ = rx.recv();
+        }
+    }
+
+    /// Write the remaining records and stop the writer thread
+    pub(crate) fn shutdown(&self) {
+        self.shared.lock().closed = true;
+        self.shared.not_empty.notify_all();
+        self.shared.not_full.notify_all();
+        let thread = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+        self.output.shutdown();
+    }
+
+    /// Queue an entry, returns it back if the writer thread is stopped
+    fn push(&self, entry: Entry) -> Option<Entry> {
+        let mut queue = self.shared.lock();
+        if entry.is_message() {
+            while queue.entries.len() >= self.shared.capacity && !queue.closed {
+                match self.shared.policy {
+                    OverflowPolicy::Block => {
+                        queue = self
+                            .shared
+                            .not_full
+                            .wait(queue)
+                            .unwrap_or_else(PoisonError::into_inner);
+                    }
+                    OverflowPolicy::DropNewest => {
+                        queue.dropped += 1;
+                        return None;
+                    }
+                    OverflowPolicy::DropOldest => {
+                        match queue.entries.iter().position(Entry::is_message) {
+                            Some(i) => {
+                                queue.entries.remove(i);
+                                queue.dropped += 1;
+                            }
+                            None => break,
+                        }
+                    }
+                }
+            }
+        }
+        if queue.closed {
+            return Some(entry);
+        }
+        queue.entries.push_back(entry);
+        drop(queue);
+        self.shared.not_empty.notify_one();
+        None
+    }
+}
+
+impl Drop for AsyncWriter {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn run(shared: &Shared, output: &Output) {
+    // The panic hook must not queue records on this thread, waiting for
+    // them to be written would never end
+    let _guard = SinkGuard::enter();
+    loop {
+        let (entries, dropped, closed) = {
+            let mut queue = shared.lock();
+            while queue.entries.is_empty() && queue.dropped == 0 && !queue.closed {
+                queue = shared
+                    .not_empty
+                    .wait(queue)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            let entries = std::mem::take(&mut queue.entries);
+            let dropped = std::mem::take(&mut queue.dropped);
+            (entries, dropped, queue.closed)
+        };
+        shared.not_full.notify_all();
+
+        if dropped > 0 {
+            let record = Record::new(WARNING, &format!("{dropped} messages dropped"));
+            let _ = std::panic::catch_unwind(AssertUnwindSafe(|| output.write_record(&record)));
+        }
+        for entry in entries {
+            // A panicking sink must not stop the thread, producers and
+            // flushes would wait for it forever. A `Flush` sender dropped
+            // by the unwinding wakes up its caller.
+            let _ = std::panic::catch_unwind(AssertUnwindSafe(|| match entry {
+                Entry::Record(record) => output.write_record(&record),
+                Entry::Raw(message) => output.write_raw(&message),
+                Entry::Flush(done) => {
+                    output.flush();
+                    let _ = done.send(());
+                }
+            }));
+        }
+        if closed {
+            break;
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Local};
+
+/// A single log event, passed to the logger output
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// Time the record was created
+    pub time: DateTime<Local>,
+    /// Log level, `TRACE`..`FATAL`
+    pub level: u32,
+    /// Name of the thread that created the record
+    pub thread: String,
+    /// Numeric id of the thread that created the record
+    pub thread_id: u64,
+    /// Target of the record, usually the module path of the caller
+    pub target: String,
+    /// Module path of the caller, if known
+    pub module_path: Option<String>,
+    /// Source file of the caller, if known
+    pub file: Option<String>,
+    /// Source line of the caller, if known
+    pub line: Option<u32>,
+    /// Log message
+    pub message: String,
+    /// Structured key-value pairs attached to the record
+    pub fields: Vec<(String, String)>,
+}
+
+impl Record {
+    /// Create a record for the current thread and time,
+    /// without any source location
+    pub fn new(level: u32, message: &str) -> Self {
+        let thread = std::thread::current();
+        Self {
+            time: Local::now(),
+            level,
+            thread: thread.name().unwrap_or("unnamed thread").to_string(),
+            thread_id: thread_id(&thread),
+            target: String::new(),
+            module_path: None,
+            file: None,
+            line: None,
+            message: message.to_string(),
+            fields: Vec::new(),
+        }
+    }
+}
+
+/// Numeric part of the thread id, `ThreadId::as_u64` is unstable
+fn thread_id(thread: &std::thread::Thread) -> u64 {
+    format!("{:?}", thread.id())
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .unwrap_or(0)
+}
@@ -0,0 +1,416 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Which old log files are kept
+///
+/// Files are discovered by matching the names in the directory of the
+/// active log file against the path pattern, every strftime specifier
+/// matches the text it can produce, e.g. `%F` matches `2024-05-01`. A
+/// pattern with unsupported specifiers matches no files. Rotated files
+/// (`name.1`, `name.2`, ...) and compressed files (`.gz`, `.zst`) match
+/// as well. The active log file is never removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// Maximum number of log files, including the active one
+    pub max_files: Option<usize>,
+    /// Maximum time since a file was last modified
+    pub max_age: Option<Duration>,
+    /// Maximum total size of the log files in bytes,
+    /// including the active one
+    pub max_total_size: Option<u64>,
+    /// Move removed files to this directory instead of deleting them
+    pub archive_dir: Option<PathBuf>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    modified: SystemTime,
+    size: u64,
+}
+
+impl Retention {
+    /// Remove or archive the files produced by `pattern` that exceed the
+    /// limits, `active` is the path of the file currently written to
+    pub(crate) fn apply(&self, pattern: &str, active: &str) {
+        let active = Path::new(active);
+        let dir = match active.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let name_pattern = match Path::new(pattern).file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => return,
+        };
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Logger: Failed to read log directory: {e}");
+                return;
+            }
+        };
+
+        let active_name = active.file_name();
+        let mut files: Vec<LogFile> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                let name = entry.file_name();
+                Some(name.as_os_str()) != active_name
+                    && matches_pattern(&name_pattern, &name.to_string_lossy())
+            })
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                metadata.is_file().then(|| LogFile {
+                    path: entry.path(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    size: metadata.len(),
+                })
+            })
+            .collect();
+        // Newest first
+        files.sort_by_key(|file| std::cmp::Reverse(file.modified));
+
+        let mut count = 1;
+        let mut total_size = std::fs::metadata(active).map(|m| m.len()).unwrap_or(0);
+        for file in files {
+            count += 1;
+            total_size += file.size;
+            let expired = self.max_files.is_some_and(|max| count > max)
+                || self.max_total_size.is_some_and(|max| total_size > max)
+                || self
+                    .max_age
+                    .is_some_and(|max| file.modified.elapsed().is_ok_and(|age| age > max));
+            if expired {
+                count -= 1;
+                total_size -= file.size;
+                self.remove(&file.path);
+            }
+        }
+    }
+
+    fn remove(&self, path: &Path) {
+        let result = match (&self.archive_dir, path.file_name()) {
+            (Some(dir), Some(name)) => std::fs::create_dir_all(dir).and_then(|_| {
+                let target = dir.join(name);
+                // Renaming fails across file systems
+                std::fs::rename(path, &target).or_else(|_| {
+                    std::fs::copy(path, &target).and_then(|_| std::fs::remove_file(path))
+                })
+            }),
+            _ => std::fs::remove_file(path),
+        };
+        if let Err(e) = result {
+            eprintln!("Logger: Failed to remove old log file: {e}");
+        }
+    }
+}
+
+/// Returns `true` if `name` was produced by the strftime `pattern`,
+/// optionally followed by a rotation and a compression suffix
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let name = [".gz", ".zst"]
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .unwrap_or(name);
+    let glob = match to_glob(pattern) {
+        Some(glob) => glob,
+        None => return false,
+    };
+    if glob_match(&glob, name.as_bytes()) {
+        return true;
+    }
+    match name.rsplit_once('.') {
+        Some((base, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+            glob_match(&glob, base.as_bytes())
+        }
+        _ => false,
+    }
+}
+
+/// Part of a file name produced by a path pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Byte(u8),
+    /// Between `min` and `max` characters of a class
+    Run(Class, usize, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Digit,
+    /// A digit or the space padding it
+    Padded,
+    Alpha,
+    /// `+` or `-` of a UTC offset
+    Sign,
+    /// A decimal point and digits
+    Fraction,
+    /// Time zone names and offsets
+    Zone,
+}
+
+impl Class {
+    fn contains(self, b: u8) -> bool {
+        match self {
+            Class::Digit => b.is_ascii_digit(),
+            Class::Padded => b.is_ascii_digit() || b == b' ',
+            Class::Alpha => b.is_ascii_alphabetic(),
+            Class::Sign => b == b'+' || b == b'-',
+            Class::Fraction => b.is_ascii_digit() || b == b'.',
+            Class::Zone => b.is_ascii_alphanumeric() || b"+-:".contains(&b),
+        }
+    }
+}
+
+/// Replace the strftime specifiers of `pattern` with the text they can
+/// produce, returns `None` for unsupported specifiers
+fn to_glob(pattern: &str) -> Option<Vec<Item>> {
+    let mut glob = Vec::new();
+    let mut bytes = pattern.bytes().peekable();
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            glob.push(Item::Byte(b));
+            continue;
+        }
+        let pad = bytes.next_if(|b| b"-_0".contains(b));
+        // Precision and offset modifiers, e.g. `%.3f`, `%:z`
+        let mut modifier = Vec::new();
+        while let Some(b) = bytes.next_if(|b| b".:#369".contains(b)) {
+            modifier.push(b);
+        }
+        specifier(&mut glob, bytes.next()?, pad, &modifier)?;
+    }
+    Some(glob)
+}
+
+fn specifier(glob: &mut Vec<Item>, spec: u8, pad: Option<u8>, modifier: &[u8]) -> Option<()> {
+    use Class::*;
+
+    let composite = match spec {
+        b'F' => Some("%Y-%m-%d"),
+        b'D' | b'x' => Some("%m/%d/%y"),
+        b'v' => Some("%e-%b-%Y"),
+        b'R' => Some("%H:%M"),
+        b'T' | b'X' => Some("%H:%M:%S"),
+        b'r' => Some("%I:%M:%S %p"),
+        b'c' => Some("%a %b %e %H:%M:%S %Y"),
+        b'+' => Some("%Y-%m-%dT%H:%M:%S%.f%:z"),
+        _ => None,
+    };
+    if let Some(composite) = composite {
+        glob.extend(to_glob(composite)?);
+        return Some(());
+    }
+
+    // Numbers are zero-padded to `width` unless the specifier pads with
+    // spaces by default, `-` removes the padding
+    let number = |width: usize, spaces: bool| {
+        let padding = pad.unwrap_or(if spaces { b'_' } else { b'0' });
+        match padding {
+            b'-' => Item::Run(Digit, 1, width),
+            b'_' => Item::Run(Padded, width, width),
+            _ => Item::Run(Digit, width, width),
+        }
+    };
+    let item = match (spec, modifier) {
+        (b'Y' | b'G', []) => number(4, false),
+        (b'C' | b'y' | b'g' | b'm' | b'd' | b'H' | b'I' | b'M' | b'S', []) => number(2, false),
+        (b'U' | b'W' | b'V', []) => number(2, false),
+        (b'e' | b'k' | b'l', []) => number(2, true),
+        (b'j', []) => number(3, false),
+        (b'w' | b'u', []) => Item::Run(Digit, 1, 1),
+        (b's', []) => Item::Run(Digit, 1, 20),
+        (b'a' | b'b' | b'h', []) => Item::Run(Alpha, 3, 3),
+        (b'A', []) => Item::Run(Alpha, 6, 9),
+        (b'B', []) => Item::Run(Alpha, 3, 9),
+        (b'p' | b'P', []) => Item::Run(Alpha, 2, 2),
+        (b'Z', []) => Item::Run(Zone, 1, 32),
+        (b'f', []) => Item::Run(Digit, 9, 9),
+        (b'f', [n @ (b'3' | b'6' | b'9')]) => {
+            let n = usize::from(n - b'0');
+            Item::Run(Digit, n, n)
+        }
+        // Omitted for whole seconds, otherwise 3, 6 or 9 digits
+        (b'f', [b'.']) => Item::Run(Fraction, 0, 10),
+        (b'f', [b'.', n @ (b'3' | b'6' | b'9')]) => {
+            glob.push(Item::Byte(b'.'));
+            let n = usize::from(n - b'0');
+            Item::Run(Digit, n, n)
+        }
+        (b'z', _) => {
+            let digits: &[usize] = match modifier {
+                [] => &[4],
+                [b':'] => &[2, 2],
+                [b':', b':'] => &[2, 2, 2],
+                [b':', b':', b':'] => &[2],
+                [b'#'] => {
+                    glob.extend([Item::Run(Sign, 1, 1), Item::Run(Digit, 2, 4)]);
+                    return Some(());
+                }
+                _ => return None,
+            };
+            glob.push(Item::Run(Sign, 1, 1));
+            for (i, &n) in digits.iter().enumerate() {
+                if i > 0 {
+                    glob.push(Item::Byte(b':'));
+                }
+                glob.push(Item::Run(Digit, n, n));
+            }
+            return Some(());
+        }
+        (b't', []) => Item::Byte(b'\t'),
+        (b'n', []) => Item::Byte(b'\n'),
+        (b'%', []) => Item::Byte(b'%'),
+        _ => return None,
+    };
+    glob.push(item);
+    Some(())
+}
+
+fn glob_match(glob: &[Item], name: &[u8]) -> bool {
+    match glob.split_first() {
+        None => name.is_empty(),
+        Some((Item::Byte(b), rest)) => name.first() == Some(b) && glob_match(rest, &name[1..]),
+        Some((&Item::Run(class, min, max), rest)) => {
+            let run = name
+                .iter()
+                .take(max)
+                .take_while(|b| class.contains(**b))
+                .count();
+            (min..=run).rev().any(|n| glob_match(rest, &name[n..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory unique to a test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("se-logger-retention-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Create the file `name` in `dir`, last modified `age` seconds ago
+    fn create(dir: &Path, name: &str, age: u64) {
+        let file = std::fs::File::create(dir.join(name)).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age))
+            .unwrap();
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn specifiers_match_what_they_produce() {
+        assert!(matches_pattern("app_%F.log", "app_2024-05-01.log"));
+        assert!(matches_pattern(
+            "app_%Y%m%d_%H-%M-%S.log",
+            "app_20240501_13-05-59.log"
+        ));
+        assert!(matches_pattern("%b_%-d.log", "May_1.log"));
+        assert!(matches_pattern("%e.log", " 1.log"));
+        assert!(matches_pattern("%A.log", "Wednesday.log"));
+        assert!(matches_pattern("app_%s%.3f.log", "app_1714561200.123.log"));
+        assert!(matches_pattern("100%%_%H.log", "100%_07.log"));
+        assert!(matches_pattern("app.log", "app.log"));
+
+        assert!(!matches_pattern("app_%F.log", "app_anything.log"));
+        assert!(!matches_pattern("app_%F.log", "app_2024-5-01.log"));
+        assert!(!matches_pattern("app_%F.log", "app_.log"));
+        assert!(!matches_pattern("app_%H.log", "app_123.log"));
+        assert!(!matches_pattern("%F", "Cargo.toml"));
+        assert!(!matches_pattern("%F", "important.txt"));
+        assert!(!matches_pattern("app.log", "app.logs"));
+    }
+
+    #[test]
+    fn rotated_and_compressed_files_match() {
+        assert!(matches_pattern("app_%F.log", "app_2024-05-01.log.1"));
+        assert!(matches_pattern("app_%F.log", "app_2024-05-01.log.12.gz"));
+        assert!(matches_pattern("app_%F.log", "app_2024-05-01.log.zst"));
+        assert!(!matches_pattern("app_%F.log", "app_2024-05-01.log.bak"));
+        assert!(!matches_pattern("app_%F.log", "app_2024-05-01.log.1.tar"));
+    }
+
+    #[test]
+    fn unsupported_specifiers_match_nothing() {
+        assert!(!matches_pattern("app_%Q.log", "app_x.log"));
+        assert!(!matches_pattern("app_%", "app_"));
+    }
+
+    #[test]
+    fn apply_removes_only_old_log_files() {
+        let dir = test_dir("max-files");
+        create(&dir, "2024-05-01", 300);
+        create(&dir, "2024-05-02.1.gz", 200);
+        create(&dir, "2024-05-02", 100);
+        create(&dir, "2024-05-03", 0);
+        create(&dir, "Cargo.toml", 400);
+        create(&dir, "important.txt", 400);
+
+        let retention = Retention {
+            max_files: Some(2),
+            ..Retention::default()
+        };
+        let active = dir.join("2024-05-03");
+        retention.apply(&dir.join("%F").to_string_lossy(), &active.to_string_lossy());
+        assert_eq!(
+            names(&dir),
+            ["2024-05-02", "2024-05-03", "Cargo.toml", "important.txt"]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn apply_keeps_the_active_file() {
+        let dir = test_dir("active");
+        create(&dir, "app.log", 1000);
+        create(&dir, "app.log.1", 10);
+
+        let retention = Retention {
+            max_age: Some(Duration::from_secs(60)),
+            max_files: Some(1),
+            ..Retention::default()
+        };
+        let active = dir.join("app.log");
+        retention.apply(&active.to_string_lossy(), &active.to_string_lossy());
+        assert_eq!(names(&dir), ["app.log"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn apply_archives_files() {
+        let dir = test_dir("archive");
+        let archive = dir.join("archive");
+        create(&dir, "app_2024-05-01.log", 300);
+        create(&dir, "app_2024-05-02.log", 200);
+        create(&dir, "app_other.log", 300);
+
+        let retention = Retention {
+            max_age: Some(Duration::from_secs(250)),
+            archive_dir: Some(archive.clone()),
+            ..Retention::default()
+        };
+        let active = dir.join("app_2024-05-03.log");
+        retention.apply(
+            &dir.join("app_%F.log").to_string_lossy(),
+            &active.to_string_lossy(),
+        );
+        assert_eq!(
+            names(&dir),
+            ["app_2024-05-02.log", "app_other.log", "archive"]
+        );
+        assert_eq!(names(&archive), ["app_2024-05-01.log"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
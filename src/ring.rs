@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::{Format, Record, Sink};
+
+/// Sink keeping the most recent records in memory
+///
+/// Meant for post-mortem debugging: registered at `TRACE` while the other
+/// sinks log at `INFO`, it holds the detailed history that is dumped when
+/// something goes wrong. The buffer is limited to a number of records or
+/// to an approximate number of bytes, the oldest records are discarded
+/// first. Clones share the buffer.
+/// ```
+/// use se_logger::*;
+///
+/// let ring = RingSink::with_records(1000);
+/// let logger = Logger::builder()
+///     .console(false)
+///     .file(false)
+///     .ring_buffer(ring.clone())
+///     .build();
+/// logger.trace("connecting");
+/// logger.error("connection lost");
+///
+/// let records = ring.records();
+/// assert_eq!(records.len(), 2);
+/// assert_eq!(records[0].message, "connecting");
+/// ```
+#[derive(Debug, Clone)]
+pub struct RingSink {
+    buffer: Arc<Mutex<Buffer>>,
+}
+
+#[derive(Debug)]
+struct Buffer {
+    records: VecDeque<Record>,
+    limit: Limit,
+    /// Approximate size of the records in bytes
+    bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Limit {
+    Records(usize),
+    Bytes(usize),
+}
+
+impl RingSink {
+    /// Keep the last `count` records
+    pub fn with_records(count: usize) -> Self {
+        Self::new(Limit::Records(count))
+    }
+
+    /// Keep the last records up to about `bytes` of messages,
+    /// targets and fields
+    pub fn with_bytes(bytes: usize) -> Self {
+        Self::new(Limit::Bytes(bytes))
+    }
+
+    fn new(limit: Limit) -> Self {
+        Self {
+            buffer: Arc::new(Mutex::new(Buffer {
+                records: VecDeque::new(),
+                limit,
+                bytes: 0,
+            })),
+        }
+    }
+
+    /// Copies of the buffered records, oldest first
+    pub fn records(&self) -> Vec<Record> {
+        self.lock().records.iter().cloned().collect()
+    }
+
+    /// Write the buffered records to the file at `path`, rendered with
+    /// `format`, replacing its contents
+    pub fn dump(&self, path: impl AsRef<Path>, format: &Format) -> io::Result<()> {
+        let records = self.records();
+        let mut file = BufWriter::new(std::fs::File::create(path)?);
+        for record in &records {
+            writeln!(file, "{}", format.format(record))?;
+        }
+        file.flush()
+    }
+
+    /// Discard the buffered records
+    pub fn clear(&self) {
+        let mut buffer = self.lock();
+        buffer.records.clear();
+        buffer.bytes = 0;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Buffer> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Sink for RingSink {
+    fn write(&self, record: &Record, _format: &Format) -> io::Result<()> {
+        let mut buffer = self.lock();
+        buffer.bytes += size(record);
+        buffer.records.push_back(record.clone());
+        while buffer.over_limit() {
+            match buffer.records.pop_front() {
+                Some(old) => buffer.bytes -= size(&old),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Buffer {
+    fn over_limit(&self) -> bool {
+        match self.limit {
+            Limit::Records(count) => self.records.len() > count,
+            Limit::Bytes(bytes) => self.bytes > bytes,
+        }
+    }
+}
+
+/// Approximate heap size of a record
+fn size(record: &Record) -> usize {
+    record.message.len()
+        + record.target.len()
+        + record.thread.len()
+        + record
+            .fields
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum::<usize>()
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::{level_to_string, Format, Record};
+
+/// Destination of log records
+///
+/// Sinks are registered with [`LoggerBuilder::sink`](crate::LoggerBuilder::sink),
+/// each with its own level and format. The built-in sinks are
+/// [`ConsoleSink`](crate::ConsoleSink), [`FileSink`](crate::FileSink),
+/// [`SyslogSink`](crate::SyslogSink), [`NetworkSink`](crate::NetworkSink)
+/// and [`RingSink`](crate::RingSink).
+/// ```no_run
+/// use se_logger::*;
+/// use std::io;
+///
+/// struct Stderr;
+///
+/// impl Sink for Stderr {
+///     fn write(&self, record: &Record, format: &Format) -> io::Result<()> {
+///         eprintln!("{}", format.format(record));
+///         Ok(())
+///     }
+/// }
+///
+/// let logger = Logger::builder()
+///     .sink(Stderr, ERROR, Format::Logfmt)
+///     .build();
+/// ```
+pub trait Sink: Send + Sync {
+    /// Write a record, rendered with `format`
+    fn write(&self, record: &Record, format: &Format) -> io::Result<()>;
+
+    /// Write buffered records
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Write buffered records and release resources,
+    /// called when the logger is shut down
+    fn close(&self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Arc<S> {
+    fn write(&self, record: &Record, format: &Format) -> io::Result<()> {
+        (**self).write(record, format)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn close(&self) -> io::Result<()> {
+        (**self).close()
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Box<S> {
+    fn write(&self, record: &Record, format: &Format) -> io::Result<()> {
+        (**self).write(record, format)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn close(&self) -> io::Result<()> {
+        (**self).close()
+    }
+}
+
+/// A registered sink with its level and format
+pub(crate) struct SinkEntry {
+    pub(crate) sink: Arc<dyn Sink>,
+    /// Changed at runtime by [`Logger::set_level`](crate::Logger::set_level)
+    pub(crate) level: AtomicU32,
+    pub(crate) format: Format,
+    /// Which of the sinks configured by the builder this is, if any
+    pub(crate) builtin: Option<Builtin>,
+}
+
+/// Sinks configured by [`LoggerBuilder`](crate::LoggerBuilder) itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    Console,
+    File,
+}
+
+impl SinkEntry {
+    pub(crate) fn new(
+        sink: Arc<dyn Sink>,
+        level: u32,
+        format: Format,
+        builtin: Option<Builtin>,
+    ) -> Self {
+        Self {
+            sink,
+            level: AtomicU32::new(level),
+            format,
+            builtin,
+        }
+    }
+
+    pub(crate) fn level(&self) -> u32 {
+        self.level.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_level(&self, level: u32) {
+        self.level.store(level, Ordering::Relaxed);
+    }
+}
+
+impl Clone for SinkEntry {
+    fn clone(&self) -> Self {
+        Self::new(
+            self.sink.clone(),
+            self.level(),
+            self.format.clone(),
+            self.builtin,
+        )
+    }
+}
+
+impl fmt::Debug for SinkEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SinkEntry")
+            .field("level", &level_to_string(self.level()))
+            .field("format", &self.format)
+            .field("builtin", &self.builtin)
+            .finish_non_exhaustive()
+    }
+}
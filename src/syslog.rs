@@ -0,0 +1,541 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::{Format, Record, Sink, ERROR, FATAL, INFO, WARNING};
+
+/// Syslog facility, the origin of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Facility {
+    Kern = 0,
+    #[default]
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// Message framing of a [`SyslogSink`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyslogProtocol {
+    /// BSD syslog: `<PRI>Mmm dd hh:mm:ss HOST APP[PID]: MSG`
+    #[default]
+    Rfc3164,
+    /// `<PRI>1 TIMESTAMP HOST APP PID - - MSG`
+    Rfc5424,
+}
+
+/// Where a [`SyslogSink`] sends messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyslogTransport {
+    /// Unix datagram socket, usually `/dev/log`
+    #[cfg(unix)]
+    Unix(PathBuf),
+    /// UDP datagrams to `host:port`
+    Udp(String),
+    /// TCP stream to `host:port`, one message per line for RFC 3164, with
+    /// line feeds in messages escaped as `#012`, and octet-counted
+    /// (RFC 6587) for RFC 5424
+    Tcp(String),
+}
+
+impl Default for SyslogTransport {
+    /// `/dev/log` on Unix, UDP to `127.0.0.1:514` elsewhere
+    fn default() -> Self {
+        #[cfg(unix)]
+        return SyslogTransport::Unix(PathBuf::from("/dev/log"));
+        #[cfg(not(unix))]
+        return SyslogTransport::Udp("127.0.0.1:514".to_string());
+    }
+}
+
+/// Sink sending records to a syslog daemon
+///
+/// The sink adds the syslog header, `format` renders the message after
+/// it, so a pattern such as `{m}` avoids repeating the time and level.
+/// Levels map to the severities debug (`TRACE`, `DEBUG`), informational,
+/// warning, error and critical (`FATAL`). The connection is opened on the
+/// first record and reopened after a failed write. While the daemon is
+/// unreachable, records are discarded and connecting is retried with
+/// exponential backoff, so logging is not blocked by every attempt.
+/// ```
+/// use se_logger::*;
+/// use std::net::UdpSocket;
+///
+/// let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let sink = SyslogSink::builder()
+///     .transport(SyslogTransport::Udp(listener.local_addr().unwrap().to_string()))
+///     .protocol(SyslogProtocol::Rfc5424)
+///     .facility(Facility::Local0)
+///     .app_name("myapp")
+///     .build();
+/// sink.write(&Record::new(ERROR, "disk full"), &Format::Pattern(Pattern::parse("{m}").unwrap()))
+///     .unwrap();
+///
+/// let mut buf = [0; 1024];
+/// let len = listener.recv(&mut buf).unwrap();
+/// let message = String::from_utf8_lossy(&buf[..len]);
+/// assert!(message.starts_with("<131>1 "));
+/// assert!(message.contains(" myapp "));
+/// assert!(message.ends_with(" - - disk full"));
+/// ```
+#[derive(Debug)]
+pub struct SyslogSink {
+    config: SyslogBuilder,
+    hostname: String,
+    state: Mutex<State>,
+}
+
+/// Builder for [`SyslogSink`]
+#[derive(Debug, Clone)]
+pub struct SyslogBuilder {
+    transport: SyslogTransport,
+    protocol: SyslogProtocol,
+    facility: Facility,
+    app_name: String,
+    hostname: Option<String>,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    timeout: Duration,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    connection: Option<Connection>,
+    /// Wait before the next connection attempt after a failure
+    backoff: Duration,
+    retry_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+enum Connection {
+    #[cfg(unix)]
+    Unix(UnixDatagram, PathBuf),
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+impl SyslogSink {
+    /// Create a builder, see [`SyslogBuilder`] for the defaults
+    pub fn builder() -> SyslogBuilder {
+        SyslogBuilder::new()
+    }
+
+    /// Build the syslog header and message for a record
+    fn message(&self, record: &Record, format: &Format) -> String {
+        let config = &self.config;
+        let pri = config.facility as u32 * 8 + severity(record.level);
+        let pid = std::process::id();
+        let mut message = String::with_capacity(128);
+        match config.protocol {
+            SyslogProtocol::Rfc3164 => {
+                let _ = write!(
+                    message,
+                    "<{pri}>{} {} {}[{pid}]: ",
+                    record.time.format("%b %e %H:%M:%S"),
+                    header_field(&self.hostname, 255),
+                    header_field(&config.app_name, 32),
+                );
+            }
+            SyslogProtocol::Rfc5424 => {
+                let _ = write!(
+                    message,
+                    "<{pri}>1 {} {} {} {pid} - - ",
+                    record.time.format("%Y-%m-%dT%H:%M:%S%.6f%:z"),
+                    header_field(&self.hostname, 255),
+                    header_field(&config.app_name, 48),
+                );
+            }
+        }
+        message.push_str(&format.format(record));
+        message
+    }
+
+    /// Send `message`, opening the connection if needed
+    fn send(&self, connection: &mut Option<Connection>, message: &[u8]) -> io::Result<()> {
+        if connection.is_none() {
+            *connection = Some(self.connect()?);
+        }
+        let result = match connection.as_mut() {
+            #[cfg(unix)]
+            Some(Connection::Unix(socket, path)) => socket.send_to(message, path).map(drop),
+            Some(Connection::Udp(socket)) => socket.send(message).map(drop),
+            Some(Connection::Tcp(stream)) => {
+                let framed = match self.config.protocol {
+                    // A line feed would end the message
+                    SyslogProtocol::Rfc3164 => {
+                        let mut framed = message
+                            .split(|&b| b == b'\n')
+                            .collect::<Vec<_>>()
+                            .join(&b"#012"[..]);
+                        framed.push(b'\n');
+                        framed
+                    }
+                    SyslogProtocol::Rfc5424 => {
+                        [format!("{} ", message.len()).as_bytes(), message].concat()
+                    }
+                };
+                stream.write_all(&framed)
+            }
+            None => Ok(()),
+        };
+        if result.is_err() {
+            *connection = None;
+        }
+        result
+    }
+
+    fn connect(&self) -> io::Result<Connection> {
+        match &self.config.transport {
+            #[cfg(unix)]
+            SyslogTransport::Unix(path) => {
+                UnixDatagram::unbound().map(|socket| Connection::Unix(socket, path.clone()))
+            }
+            SyslogTransport::Udp(addr) => {
+                let socket = UdpSocket::bind(if addr.starts_with('[') {
+                    "[::]:0"
+                } else {
+                    "0.0.0.0:0"
+                })?;
+                socket.connect(addr)?;
+                Ok(Connection::Udp(socket))
+            }
+            SyslogTransport::Tcp(addr) => {
+                let addr = addr
+                    .to_socket_addrs()?
+                    .next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address not found"))?;
+                let stream = TcpStream::connect_timeout(&addr, self.config.timeout)?;
+                stream.set_write_timeout(Some(self.config.timeout))?;
+                Ok(Connection::Tcp(stream))
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Sink for SyslogSink {
+    /// Send a record, discards it while waiting to reconnect
+    fn write(&self, record: &Record, format: &Format) -> io::Result<()> {
+        let mut state = self.lock();
+        let state = &mut *state;
+        if state.connection.is_none() && state.retry_at.is_some_and(|at| Instant::now() < at) {
+            return Ok(());
+        }
+        let message = self.message(record, format);
+        // A daemon restart closes the connection, retry once with a new one
+        let connected = state.connection.is_some();
+        let mut result = self.send(&mut state.connection, message.as_bytes());
+        if result.is_err() && connected {
+            result = self.send(&mut state.connection, message.as_bytes());
+        }
+        match result {
+            Ok(()) => {
+                state.backoff = Duration::ZERO;
+                state.retry_at = None;
+            }
+            Err(_) => {
+                state.backoff =
+                    (state.backoff * 2).clamp(self.config.min_backoff, self.config.max_backoff);
+                state.retry_at = Some(Instant::now() + state.backoff);
+            }
+        }
+        result
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        match self.lock().connection.as_mut() {
+            Some(Connection::Tcp(stream)) => stream.flush(),
+            _ => Ok(()),
+        }
+    }
+
+    fn close(&self) -> io::Result<()> {
+        let result = self.flush();
+        self.lock().connection = None;
+        result
+    }
+}
+
+impl SyslogBuilder {
+    /// Create a builder with the defaults: the default transport,
+    /// RFC 3164, [`Facility::User`] and the executable name as app name
+    pub fn new() -> Self {
+        Self {
+            transport: SyslogTransport::default(),
+            protocol: SyslogProtocol::default(),
+            facility: Facility::default(),
+            app_name: app_name(),
+            hostname: None,
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// Where messages are sent
+    pub fn transport(mut self, transport: SyslogTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Message framing, RFC 3164 by default
+    pub fn protocol(mut self, protocol: SyslogProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Facility of every message, [`Facility::User`] by default
+    pub fn facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Name of the application, the executable name by default
+    pub fn app_name(mut self, app_name: &str) -> Self {
+        self.app_name = app_name.to_string();
+        self
+    }
+
+    /// Host name in the header, detected by default
+    pub fn hostname(mut self, hostname: &str) -> Self {
+        self.hostname = Some(hostname.to_string());
+        self
+    }
+
+    /// Wait between connection attempts after a failure, doubling from
+    /// `min` up to `max`. 100 ms and 30 s by default.
+    pub fn backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    /// Timeout for connecting and sending over TCP, 1 s by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Build the sink, the connection is opened on the first record
+    pub fn build(self) -> SyslogSink {
+        SyslogSink {
+            hostname: self.hostname.clone().unwrap_or_else(hostname),
+            config: self,
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl Default for SyslogBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Syslog severity of a level
+pub(crate) fn severity(level: u32) -> u32 {
+    match level {
+        FATAL => 2,
+        ERROR => 3,
+        WARNING => 4,
+        INFO => 6,
+        // DEBUG and TRACE
+        _ => 7,
+    }
+}
+
+/// A header field limited to printable ASCII and `max` characters,
+/// `-` if empty
+fn header_field(value: &str, max: usize) -> String {
+    let field: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max)
+        .collect();
+    match field.as_str() {
+        "" => "-".to_string(),
+        _ => field,
+    }
+}
+
+/// Name of the executable, without extension
+pub(crate) fn app_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "se-logger".to_string())
+}
+
+fn hostname() -> String {
+    let name = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .unwrap_or_default();
+    match name.trim() {
+        "" => "localhost".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pattern;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn format() -> Format {
+        Format::Pattern(Pattern::parse("{m}").unwrap())
+    }
+
+    fn builder(transport: SyslogTransport) -> SyslogBuilder {
+        SyslogSink::builder()
+            .transport(transport)
+            .app_name("app")
+            .hostname("host")
+    }
+
+    fn log(sink: &SyslogSink, message: &str) -> io::Result<()> {
+        sink.write(&Record::new(INFO, message), &format())
+    }
+
+    fn accept(listener: &TcpListener) -> TcpStream {
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        stream
+    }
+
+    /// Address of a TCP port that nothing listens on
+    fn closed_port() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    #[test]
+    fn rfc3164_over_tcp_escapes_line_feeds() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let sink = builder(SyslogTransport::Tcp(addr)).build();
+        log(&sink, "first\nsecond").unwrap();
+        log(&sink, "third").unwrap();
+
+        let pid = std::process::id();
+        let lines: Vec<String> = BufReader::new(accept(&listener))
+            .lines()
+            .take(2)
+            .map(Result::unwrap)
+            .collect();
+        assert!(lines[0].starts_with("<14>"));
+        assert!(lines[0].ends_with(&format!(" host app[{pid}]: first#012second")));
+        assert!(lines[1].ends_with(&format!(" host app[{pid}]: third")));
+    }
+
+    #[test]
+    fn rfc5424_over_tcp_counts_octets() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let sink = builder(SyslogTransport::Tcp(addr))
+            .protocol(SyslogProtocol::Rfc5424)
+            .build();
+        log(&sink, "first\nsecond").unwrap();
+        log(&sink, "third").unwrap();
+
+        let pid = std::process::id();
+        let mut stream = BufReader::new(accept(&listener));
+        for expected in ["first\nsecond", "third"] {
+            let mut len = Vec::new();
+            stream.read_until(b' ', &mut len).unwrap();
+            let len: usize = std::str::from_utf8(&len).unwrap().trim().parse().unwrap();
+            let mut message = vec![0; len];
+            stream.read_exact(&mut message).unwrap();
+            let message = String::from_utf8(message).unwrap();
+            assert!(message.starts_with("<14>1 "));
+            assert!(message.ends_with(&format!(" host app {pid} - - {expected}")));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_datagrams() {
+        let path = std::env::temp_dir().join(format!("se-logger-syslog-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let socket = UnixDatagram::bind(&path).unwrap();
+        socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let sink = builder(SyslogTransport::Unix(path.clone()))
+            .facility(Facility::Local0)
+            .build();
+        sink.write(&Record::new(ERROR, "disk\nfull"), &format())
+            .unwrap();
+
+        let mut buf = [0; 1024];
+        let len = socket.recv(&mut buf).unwrap();
+        let message = String::from_utf8_lossy(&buf[..len]);
+        assert!(message.starts_with("<131>"));
+        // Datagrams need no escaping
+        assert!(message.ends_with("]: disk\nfull"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn backs_off_after_failed_connection() {
+        let addr = closed_port();
+        let sink = builder(SyslogTransport::Tcp(addr.clone()))
+            .backoff(Duration::from_millis(200), Duration::from_secs(1))
+            .build();
+        assert!(log(&sink, "refused").is_err());
+
+        // Discarded without connecting until the backoff has passed
+        let listener = TcpListener::bind(&addr).unwrap();
+        log(&sink, "discarded").unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+        log(&sink, "sent").unwrap();
+
+        let mut line = String::new();
+        BufReader::new(accept(&listener))
+            .read_line(&mut line)
+            .unwrap();
+        assert!(line.ends_with("]: sent\n"));
+    }
+
+    #[test]
+    fn unreachable_daemon_does_not_block() {
+        // Not routable, connecting either fails at once or times out
+        let sink = builder(SyslogTransport::Tcp("10.255.255.1:514".to_string()))
+            .timeout(Duration::from_millis(200))
+            .build();
+        let start = Instant::now();
+        for _ in 0..10 {
+            let _ = log(&sink, "lost");
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
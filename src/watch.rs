@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use crate::{global, replace_global, sync_max_level, Config, ConfigError, LoggerBuilder};
+
+type Customize = Box<dyn Fn(LoggerBuilder) -> LoggerBuilder + Send>;
+
+/// Reloads the process-global logger when its config file changes
+///
+/// The file is polled for changes of its modification time and size.
+/// If only levels or filters changed, they are applied to the running
+/// logger. Otherwise a new logger is built and installed, and the previous
+/// one is shut down after the switch, so records logged meanwhile are
+/// written by exactly one of them. Invalid files are reported and the
+/// current settings are kept.
+///
+/// `customize` runs on every reload, including the ones that only change
+/// levels and discard the builder. It must not create sinks: build them
+/// once and add them with [`LoggerBuilder::shared_sink`], then they stay
+/// open across reloads. Other sinks are closed with the previous logger.
+///
+/// Levels set with [`set_level`](crate::set_level) are replaced on the
+/// next reload. Dropping the watcher stops polling and keeps the logger.
+/// ```no_run
+/// use se_logger::*;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let network: Arc<dyn Sink> =
+///     Arc::new(NetworkSink::builder("logs.example.com:5170").build());
+///
+/// // Settings made in code take precedence over the file
+/// let watcher = ConfigWatcher::spawn("logging.toml", Duration::from_secs(2), move |builder| {
+///     builder
+///         .color(ColorChoice::Never)
+///         .shared_sink(network.clone(), INFO, Format::Json)
+/// })
+/// .unwrap();
+/// ```
+pub struct ConfigWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct State {
+    path: PathBuf,
+    config: Config,
+    stamp: Option<(SystemTime, u64)>,
+    customize: Customize,
+}
+
+impl ConfigWatcher {
+    /// Install a process-global logger configured by the file at `path`
+    /// and check the file for changes every `interval`
+    ///
+    /// `customize` is applied to the builder created with
+    /// [`LoggerBuilder::from_config`] on every reload and must only add
+    /// sinks built beforehand, see [`ConfigWatcher`]. Fails if the file
+    /// cannot be loaded initially.
+    pub fn spawn(
+        path: impl AsRef<Path>,
+        interval: Duration,
+        customize: impl Fn(LoggerBuilder) -> LoggerBuilder + Send + 'static,
+    ) -> Result<Self, ConfigError> {
+        let path = path.as_ref().to_path_buf();
+        let stamp = stamp(&path);
+        let config = Config::load(&path)?;
+        customize(LoggerBuilder::from_config(&config))
+            .build()
+            .install();
+
+        let mut state = State {
+            path,
+            config,
+            stamp,
+            customize: Box::new(customize),
+        };
+        let (tx, rx) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("se-logger config".to_string())
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(interval) {
+                    state.poll();
+                }
+            });
+        let thread = match thread {
+            Ok(thread) => Some(thread),
+            Err(e) => {
+                eprintln!("Logger: Failed to start config watcher thread: {e}");
+                None
+            }
+        };
+        Ok(Self {
+            stop: Some(tx),
+            thread,
+        })
+    }
+
+    /// Stop polling and wait for a running reload to finish
+    pub fn stop(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        // Disconnecting the channel wakes up the thread
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+impl std::fmt::Debug for ConfigWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigWatcher").finish_non_exhaustive()
+    }
+}
+
+impl State {
+    /// Reload the config if the file changed
+    fn poll(&mut self) {
+        let stamp = stamp(&self.path);
+        if stamp.is_none() || stamp == self.stamp {
+            return;
+        }
+        self.stamp = stamp;
+        let config = match Config::load(&self.path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Logger: Failed to reload config: {e}");
+                return;
+            }
+        };
+        let builder = (self.customize)(LoggerBuilder::from_config(&config));
+        if config.same_sinks(&self.config) {
+            let logger = global();
+            let (console, file) = builder.levels();
+            logger.set_levels(console, file, builder.filter_ref().cloned());
+            sync_max_level(&logger);
+        } else {
+            let logger = builder.build();
+            let shared = logger.sinks();
+            if let Some(old) = replace_global(logger) {
+                // Records still being logged to the old logger are written
+                // synchronously after the shutdown
+                old.shutdown_keeping(shared);
+            }
+        }
+        self.config = config;
+    }
+}
+
+/// Modification time and size of the file at `path`
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}